@@ -1,12 +1,15 @@
 mod action_rules;
+pub mod attacks;
+mod notation;
 
 #[cfg(test)]
 mod action_tests;
 
 use crate::{BoardPosition, BoardState, Piece, PieceColor, PieceType};
 use action_rules::ActionRule;
+pub use notation::NotationError;
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum ActionType {
     SimpleMove {
         from: BoardPosition,
@@ -19,9 +22,14 @@ pub enum ActionType {
         from: BoardPosition,
         to: BoardPosition,
     },
+    Promotion {
+        from: BoardPosition,
+        to: BoardPosition,
+        piece_type: PieceType,
+    },
 }
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub struct Action {
     action_type: ActionType,
 }
@@ -32,23 +40,35 @@ impl Action {
     }
 
     pub fn play_move(&self, board_state: &mut BoardState) {
+        board_state.record_move(self.is_irreversible(board_state));
+        let previous_state_key = board_state.state_key();
         // reset the en_passant to some colunm that will never be reached by the possible move finder
         board_state.en_passant_colunm = 55;
         let color = board_state.color_turn;
         board_state.color_turn = color.opposite_color();
 
+        // a rook moving from or being captured on its starting square can no longer castle
+        match self.action_type {
+            ActionType::SimpleMove { from, to }
+            | ActionType::Promotion {
+                from,
+                to,
+                piece_type: _,
+            } => {
+                remove_castling_rights(board_state, from);
+                remove_castling_rights(board_state, to);
+            }
+            _ => (),
+        }
+
         match self.action_type {
             ActionType::SimpleMove { from, to } => {
-                let from_piece_ref = board_state.get_mut(from);
-                let from_piece = from_piece_ref.clone();
-                *from_piece_ref = None;
-                let mut result_piece = from_piece;
+                let from_piece = *board_state.get(from);
+                board_state.set(from, None);
                 match from_piece.unwrap().piece_type {
                     PieceType::Pawn => {
                         if (from.y == 1 && to.y == 3) || (from.y == 6 && to.y == 4) {
                             board_state.en_passant_colunm = to.x;
-                        } else if to.y == 0 || to.y == 7 {
-                            result_piece = Some(Piece::new(color, PieceType::Queen));
                         }
                     }
                     PieceType::King => {
@@ -60,28 +80,9 @@ impl Action {
                             board_state.black_queen_castle = false;
                         }
                     }
-                    PieceType::Rook => {
-                        if color == PieceColor::White {
-                            if from.y == 0 {
-                                if from.x == 0 {
-                                    board_state.white_queen_castle = false;
-                                } else if from.x == 7 {
-                                    board_state.white_king_castle = false;
-                                }
-                            }
-                        } else {
-                            if from.y == 7 {
-                                if from.x == 0 {
-                                    board_state.black_queen_castle = false;
-                                } else if from.x == 7 {
-                                    board_state.black_king_castle = false;
-                                }
-                            }
-                        }
-                    }
                     _ => (),
                 }
-                *board_state.get_mut(to) = result_piece;
+                board_state.set(to, from_piece);
             }
             ActionType::Castling { kings_side } => {
                 let y_row = if color == PieceColor::White {
@@ -93,36 +94,91 @@ impl Action {
                     board_state.black_queen_castle = false;
                     7
                 };
-                *board_state.get_mut(BoardPosition::new(4, y_row)) = None;
+                board_state.set(BoardPosition::new(4, y_row), None);
                 if kings_side {
-                    *board_state.get_mut(BoardPosition::new(7, y_row)) = None;
-                    *board_state.get_mut(BoardPosition::new(5, y_row)) =
-                        Some(Piece::new(color, PieceType::Rook));
-                    *board_state.get_mut(BoardPosition::new(6, y_row)) =
-                        Some(Piece::new(color, PieceType::King));
+                    board_state.set(BoardPosition::new(7, y_row), None);
+                    board_state.set(
+                        BoardPosition::new(5, y_row),
+                        Some(Piece::new(color, PieceType::Rook)),
+                    );
+                    board_state.set(
+                        BoardPosition::new(6, y_row),
+                        Some(Piece::new(color, PieceType::King)),
+                    );
                 } else {
-                    *board_state.get_mut(BoardPosition::new(0, y_row)) = None;
-                    *board_state.get_mut(BoardPosition::new(3, y_row)) =
-                        Some(Piece::new(color, PieceType::Rook));
-                    *board_state.get_mut(BoardPosition::new(2, y_row)) =
-                        Some(Piece::new(color, PieceType::King));
+                    board_state.set(BoardPosition::new(0, y_row), None);
+                    board_state.set(
+                        BoardPosition::new(3, y_row),
+                        Some(Piece::new(color, PieceType::Rook)),
+                    );
+                    board_state.set(
+                        BoardPosition::new(2, y_row),
+                        Some(Piece::new(color, PieceType::King)),
+                    );
                 }
             }
             ActionType::EnPassant { from, to } => {
-                *board_state.get_mut(to) = Some(Piece::new(color, PieceType::Pawn));
-                *board_state.get_mut(from) = None;
-                *board_state.get_mut(BoardPosition::new(to.x, from.y)) = None;
+                board_state.set(to, Some(Piece::new(color, PieceType::Pawn)));
+                board_state.set(from, None);
+                board_state.set(BoardPosition::new(to.x, from.y), None);
+            }
+            ActionType::Promotion {
+                from,
+                to,
+                piece_type,
+            } => {
+                board_state.set(from, None);
+                board_state.set(to, Some(Piece::new(color, piece_type)));
             }
         }
+        board_state.update_state_key(previous_state_key);
+    }
+    // captures and pawn moves reset the fifty-move rule and mean no earlier position can be repeated
+    fn is_irreversible(&self, board_state: &BoardState) -> bool {
+        match self.action_type {
+            ActionType::SimpleMove { from, to } => {
+                board_state.get(to).is_some()
+                    || board_state.get(from).unwrap().piece_type == PieceType::Pawn
+            }
+            ActionType::Castling { kings_side: _ } => false,
+            ActionType::EnPassant { from: _, to: _ } => true,
+            ActionType::Promotion {
+                from: _,
+                to: _,
+                piece_type: _,
+            } => true,
+        }
+    }
+    // the piece taken by this action, if it is a capture
+    pub fn captured_piece(&self, board_state: &BoardState) -> Option<Piece> {
+        match self.action_type {
+            ActionType::SimpleMove { from: _, to }
+            | ActionType::Promotion {
+                from: _,
+                to,
+                piece_type: _,
+            } => *board_state.get(to),
+            ActionType::EnPassant { from: _, to: _ } => Some(Piece::new(
+                board_state.color_turn.opposite_color(),
+                PieceType::Pawn,
+            )),
+            ActionType::Castling { kings_side: _ } => None,
+        }
     }
     pub fn get_action_type(&self) -> ActionType {
         self.action_type
     }
 }
-pub fn find_legal_actions(
-    board_state: &BoardState,
-    safe: bool,
-) -> (Vec<Action>, Option<GameEndState>) {
+fn remove_castling_rights(board_state: &mut BoardState, rook_pos: BoardPosition) {
+    match (rook_pos.x, rook_pos.y) {
+        (0, 0) => board_state.white_queen_castle = false,
+        (7, 0) => board_state.white_king_castle = false,
+        (0, 7) => board_state.black_queen_castle = false,
+        (7, 7) => board_state.black_king_castle = false,
+        _ => (),
+    }
+}
+pub fn find_legal_actions(board_state: &BoardState) -> (Vec<Action>, Option<GameEndState>) {
     let mut legal_actions = Vec::with_capacity(35);
     action_rules::PawnActions::update_actions(&board_state, &mut legal_actions);
     action_rules::KnightActions::update_actions(&board_state, &mut legal_actions);
@@ -131,58 +187,33 @@ pub fn find_legal_actions(
     action_rules::KingActions::update_actions(&board_state, &mut legal_actions);
     action_rules::CastlingActions::update_actions(&board_state, &mut legal_actions);
     action_rules::RemoveIllegalActions::update_actions(&board_state, &mut legal_actions);
-    if safe {
-        action_rules::RemoveUnsafeActions::update_actions(&board_state, &mut legal_actions);
-    }
     let game_end_state_option = if legal_actions.is_empty() {
         let opponent_color = board_state.color_turn.opposite_color();
-        let mut opponent_turn_board_state = board_state.clone();
-        opponent_turn_board_state.color_turn = opponent_color;
-        if in_check(&opponent_turn_board_state) {
+        if action_rules::king_attacked(board_state, board_state.color_turn) {
             Some(GameEndState::Win(opponent_color))
         } else {
             Some(GameEndState::Draw)
         }
+    } else if board_state.halfmove_clock >= 100 || board_state.repetition_count() >= 2 {
+        // fifty-move rule or threefold repetition
+        Some(GameEndState::Draw)
     } else {
         None
     };
     (legal_actions, game_end_state_option)
 }
 
-pub fn in_check(board_state: &BoardState) -> bool {
-    // TODO: optomize?
-
-    let king_color = board_state.color_turn.opposite_color();
-    let mut king_pos = None;
-    for y in 0..8 {
-        for x in 0..8 {
-            if let Some(piece) = board_state.get(BoardPosition::new(x, y)) {
-                if piece.piece_type == PieceType::King && piece.color == king_color {
-                    king_pos = Some(BoardPosition::new(x, y));
-                }
-            }
-        }
-    }
+// whether the king of the color to move is attacked
+pub fn side_to_move_in_check(board_state: &BoardState) -> bool {
+    action_rules::king_attacked(board_state, board_state.color_turn)
+}
 
-    if let Some(king_pos) = king_pos {
-        let possible_opponent_moves = find_legal_actions(&board_state, false).0;
-        for possible_move in possible_opponent_moves {
-            match possible_move.action_type {
-                ActionType::SimpleMove { from: _, to } => {
-                    if king_pos == to {
-                        return true;
-                    }
-                }
-                _ => (),
-            }
-        }
-        return false;
-    } else {
-        panic!("this color has no king");
-    }
+// whether the color to move could capture the other colors king
+pub fn in_check(board_state: &BoardState) -> bool {
+    action_rules::king_attacked(board_state, board_state.color_turn.opposite_color())
 }
 
-#[derive(Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum GameEndState {
     Win(PieceColor),
     Draw,
@@ -1,8 +1,8 @@
-use super::find_legal_actions;
-use crate::{Action, ActionType, BoardPosition, BoardState, PieceType};
+use super::{find_legal_actions, GameEndState};
+use crate::{Action, ActionType, BoardPosition, BoardState, PieceColor, PieceType};
 
 fn test_move(board_state: BoardState, action: Action) -> BoardState {
-    let legal_moves = find_legal_actions(&board_state, false).0;
+    let legal_moves = find_legal_actions(&board_state).0;
     assert!(
         legal_moves.contains(&action),
         format!(
@@ -18,14 +18,16 @@ fn test_move(board_state: BoardState, action: Action) -> BoardState {
 #[test]
 fn en_passant() {
     test_move(
-        BoardState::from_fen("rnbqkbnr/1pppp1pp/p7/4Pp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3"),
+        BoardState::from_fen("rnbqkbnr/1pppp1pp/p7/4Pp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3")
+            .unwrap(),
         Action::new(ActionType::EnPassant {
             from: BoardPosition::from_text("e5"),
             to: BoardPosition::from_text("f6"),
         }),
     );
     test_move(
-        BoardState::from_fen("rnbqkbnr/ppp1pppp/8/8/3pP2P/8/PPPP1PP1/RNBQKBNR b KQkq e3 0 3"),
+        BoardState::from_fen("rnbqkbnr/ppp1pppp/8/8/3pP2P/8/PPPP1PP1/RNBQKBNR b KQkq e3 0 3")
+            .unwrap(),
         Action::new(ActionType::EnPassant {
             from: BoardPosition::from_text("d4"),
             to: BoardPosition::from_text("e3"),
@@ -38,10 +40,11 @@ fn queening_test() {
     let assert_queen_made = |board_fen: &str, from_str: &str, to_str: &str| {
         assert!(
             test_move(
-                BoardState::from_fen(board_fen),
-                Action::new(ActionType::SimpleMove {
+                BoardState::from_fen(board_fen).unwrap(),
+                Action::new(ActionType::Promotion {
                     from: BoardPosition::from_text(from_str),
                     to: BoardPosition::from_text(to_str),
+                    piece_type: PieceType::Queen,
                 }),
             )
             .get(BoardPosition::from_text(to_str))
@@ -85,10 +88,46 @@ fn queening_test() {
     );
 }
 
+#[test]
+fn underpromotion() {
+    let board_state =
+        BoardState::from_fen("rnbqkb1r/1pppppPp/5n2/8/p7/8/PPPPP1PP/RNBQKBNR w KQkq - 1 5")
+            .unwrap();
+    let legal_moves = find_legal_actions(&board_state).0;
+    let from = BoardPosition::from_text("g7");
+    let to = BoardPosition::from_text("h8");
+    for piece_type in [
+        PieceType::Queen,
+        PieceType::Knight,
+        PieceType::Rook,
+        PieceType::Bishop,
+    ]
+    .iter()
+    {
+        let promoted_piece = test_move(
+            board_state.clone(),
+            Action::new(ActionType::Promotion {
+                from,
+                to,
+                piece_type: *piece_type,
+            }),
+        )
+        .get(to)
+        .unwrap();
+        assert!(promoted_piece.piece_type == *piece_type);
+        assert!(promoted_piece.color == PieceColor::White);
+    }
+    assert!(
+        !legal_moves.contains(&Action::new(ActionType::SimpleMove { from, to })),
+        "pawn moved onto the last rank without promoting"
+    );
+}
+
 #[test]
 fn castling() {
     let white_king_castled = test_move(
-        BoardState::from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4"),
+        BoardState::from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4")
+            .unwrap(),
         Action::new(ActionType::Castling { kings_side: true }),
     );
     assert!(
@@ -112,3 +151,55 @@ fn castling() {
         .get(BoardPosition::from_text("h1"))
         .is_none());
 }
+
+#[test]
+fn threefold_repetition() {
+    let knight_moves = [("g1", "f3"), ("g8", "f6"), ("f3", "g1"), ("f6", "g8")];
+    let mut board_state = BoardState::default();
+    for i in 0..8 {
+        assert!(
+            find_legal_actions(&board_state).1.is_none(),
+            "game ended before the position was repeated three times"
+        );
+        let (from, to) = knight_moves[i % knight_moves.len()];
+        board_state = test_move(
+            board_state,
+            Action::new(ActionType::SimpleMove {
+                from: BoardPosition::from_text(from),
+                to: BoardPosition::from_text(to),
+            }),
+        );
+    }
+    assert_eq!(board_state.repetition_count(), 2);
+    assert!(matches!(
+        find_legal_actions(&board_state).1,
+        Some(GameEndState::Draw)
+    ));
+}
+
+#[test]
+fn fifty_move_rule() {
+    let board_state = BoardState::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 99 80").unwrap();
+    assert!(find_legal_actions(&board_state).1.is_none());
+    let king_moved = test_move(
+        board_state.clone(),
+        Action::new(ActionType::SimpleMove {
+            from: BoardPosition::from_text("e1"),
+            to: BoardPosition::from_text("d1"),
+        }),
+    );
+    assert_eq!(king_moved.halfmove_clock, 100);
+    assert!(matches!(
+        find_legal_actions(&king_moved).1,
+        Some(GameEndState::Draw)
+    ));
+    let pawn_moved = test_move(
+        board_state,
+        Action::new(ActionType::SimpleMove {
+            from: BoardPosition::from_text("e2"),
+            to: BoardPosition::from_text("e3"),
+        }),
+    );
+    assert_eq!(pawn_moved.halfmove_clock, 0);
+    assert!(find_legal_actions(&pawn_moved).1.is_none());
+}
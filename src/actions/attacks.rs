@@ -0,0 +1,146 @@
+use crate::board_state::bitboard::{position_bitboard, Bitboard};
+use crate::{BoardPosition, BoardState, PieceColor, PieceType};
+
+const KNIGHT_OFFSETS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+const KING_OFFSETS: [(i8, i8); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+const WHITE_PAWN_OFFSETS: [(i8, i8); 2] = [(-1, 1), (1, 1)];
+const BLACK_PAWN_OFFSETS: [(i8, i8); 2] = [(-1, -1), (1, -1)];
+// the directions of the lines through a square, in the order they are stored in LINE_MASKS
+const LINE_DIRECTIONS: [(i8, i8); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+const FILE: usize = 0;
+const RANK: usize = 1;
+const DIAGONAL: usize = 2;
+const ANTI_DIAGONAL: usize = 3;
+
+// the tables are generated at compile time so no work is needed when the engine starts
+static KNIGHT_ATTACKS: [Bitboard; 64] = offset_attacks(&KNIGHT_OFFSETS);
+static KING_ATTACKS: [Bitboard; 64] = offset_attacks(&KING_OFFSETS);
+static PAWN_ATTACKS: [[Bitboard; 64]; 2] = [
+    offset_attacks(&WHITE_PAWN_OFFSETS),
+    offset_attacks(&BLACK_PAWN_OFFSETS),
+];
+// the file, rank, diagonal and anti diagonal through each square, not including the square itself
+static LINE_MASKS: [[Bitboard; 4]; 64] = line_masks();
+
+const fn offset_attacks(offsets: &[(i8, i8)]) -> [Bitboard; 64] {
+    let mut attacks = [0; 64];
+    let mut square = 0;
+    while square < 64 {
+        let x = (square % 8) as i8;
+        let y = (square / 8) as i8;
+        let mut i = 0;
+        while i < offsets.len() {
+            let to_x = x + offsets[i].0;
+            let to_y = y + offsets[i].1;
+            if to_x >= 0 && to_x < 8 && to_y >= 0 && to_y < 8 {
+                attacks[square] |= 1 << (to_y * 8 + to_x);
+            }
+            i += 1;
+        }
+        square += 1;
+    }
+    attacks
+}
+
+const fn line_masks() -> [[Bitboard; 4]; 64] {
+    let mut masks = [[0; 4]; 64];
+    let mut square = 0;
+    while square < 64 {
+        let mut line = 0;
+        while line < 4 {
+            let (dx, dy) = LINE_DIRECTIONS[line];
+            // walk away from the square in both directions along the line
+            let mut sign = -1;
+            while sign <= 1 {
+                let mut x = (square % 8) as i8 + dx * sign;
+                let mut y = (square / 8) as i8 + dy * sign;
+                while x >= 0 && x < 8 && y >= 0 && y < 8 {
+                    masks[square][line] |= 1 << (y * 8 + x);
+                    x += dx * sign;
+                    y += dy * sign;
+                }
+                sign += 2;
+            }
+            line += 1;
+        }
+        square += 1;
+    }
+    masks
+}
+
+// hyperbola quintessence: subtracting twice the slider from the blockers on a line
+// flips every bit up to and including the first blocker, doing the same on the
+// reversed board gives the attacks in the other direction
+fn line_attacks(pos: BoardPosition, occupied: Bitboard, mask: Bitboard) -> Bitboard {
+    let slider = position_bitboard(pos);
+    let blockers = occupied & mask;
+    let forward = blockers.wrapping_sub(slider.wrapping_mul(2));
+    let reverse = blockers
+        .reverse_bits()
+        .wrapping_sub(slider.reverse_bits().wrapping_mul(2))
+        .reverse_bits();
+    (forward ^ reverse) & mask
+}
+
+pub fn knight_attacks(pos: BoardPosition) -> Bitboard {
+    KNIGHT_ATTACKS[pos.to_index() as usize]
+}
+
+pub fn king_attacks(pos: BoardPosition) -> Bitboard {
+    KING_ATTACKS[pos.to_index() as usize]
+}
+
+// the squares a pawn of the given color on this position could capture on
+pub fn pawn_attacks(color: PieceColor, pos: BoardPosition) -> Bitboard {
+    PAWN_ATTACKS[color as usize][pos.to_index() as usize]
+}
+
+pub fn bishop_attacks(pos: BoardPosition, occupied: Bitboard) -> Bitboard {
+    let masks = &LINE_MASKS[pos.to_index() as usize];
+    line_attacks(pos, occupied, masks[DIAGONAL]) | line_attacks(pos, occupied, masks[ANTI_DIAGONAL])
+}
+
+pub fn rook_attacks(pos: BoardPosition, occupied: Bitboard) -> Bitboard {
+    let masks = &LINE_MASKS[pos.to_index() as usize];
+    line_attacks(pos, occupied, masks[FILE]) | line_attacks(pos, occupied, masks[RANK])
+}
+
+// whether any piece of the attacking color attacks the position
+// occupied and captured describe the board after a move that hasnt been played yet,
+// captured pieces can not attack anything
+pub fn is_attacked(
+    board_state: &BoardState,
+    pos: BoardPosition,
+    attacking_color: PieceColor,
+    occupied: Bitboard,
+    captured: Bitboard,
+) -> bool {
+    let attackers = |piece_type: PieceType| -> Bitboard {
+        board_state.piece_bitboard(attacking_color, piece_type) & !captured
+    };
+    let queens = attackers(PieceType::Queen);
+    knight_attacks(pos) & attackers(PieceType::Knight) != 0
+        || king_attacks(pos) & attackers(PieceType::King) != 0
+        // a pawn attacks this position if a pawn of the other color here would attack the pawn
+        || pawn_attacks(attacking_color.opposite_color(), pos) & attackers(PieceType::Pawn) != 0
+        || bishop_attacks(pos, occupied) & (attackers(PieceType::Bishop) | queens) != 0
+        || rook_attacks(pos, occupied) & (attackers(PieceType::Rook) | queens) != 0
+}
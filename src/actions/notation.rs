@@ -0,0 +1,272 @@
+#[cfg(test)]
+mod notation_tests;
+
+use super::{find_legal_actions, side_to_move_in_check, Action, ActionType, GameEndState};
+use crate::{BoardPosition, BoardState, Piece, PieceColor, PieceType};
+use std::fmt;
+
+impl Action {
+    // standard algebraic notation as used in pgn, like "Nf3", "exd6", "O-O-O" or "e8=N+",
+    // it follows the pgn export format so en passant captures have no "e.p." suffix
+    pub fn to_san(&self, board_state: &BoardState) -> String {
+        let mut san = match self.squares() {
+            None => match self.action_type {
+                ActionType::Castling { kings_side: true } => "O-O".to_string(),
+                _ => "O-O-O".to_string(),
+            },
+            Some((from, to)) => {
+                let piece_type = board_state
+                    .get(from)
+                    .expect("there is no piece to move")
+                    .piece_type;
+                let capture = self.captured_piece(board_state).is_some();
+                let from_text = from.to_text();
+                let (file, rank) = from_text.split_at(1);
+                let mut san = String::with_capacity(7);
+                if piece_type == PieceType::Pawn {
+                    if capture {
+                        san.push_str(file);
+                    }
+                } else {
+                    san.push(san_piece_char(piece_type));
+                    // other pieces of the same type that can move to the same square
+                    let others: Vec<BoardPosition> = find_legal_actions(board_state)
+                        .0
+                        .iter()
+                        .filter_map(Action::squares)
+                        .filter(|(other_from, other_to)| {
+                            *other_to == to
+                                && *other_from != from
+                                && board_state.get(*other_from).map(|piece| piece.piece_type)
+                                    == Some(piece_type)
+                        })
+                        .map(|(other_from, _)| other_from)
+                        .collect();
+                    if !others.is_empty() {
+                        if others.iter().all(|other| other.x != from.x) {
+                            san.push_str(file);
+                        } else if others.iter().all(|other| other.y != from.y) {
+                            san.push_str(rank);
+                        } else {
+                            san.push_str(file);
+                            san.push_str(rank);
+                        }
+                    }
+                }
+                if capture {
+                    san.push('x');
+                }
+                san.push_str(&to.to_text());
+                if let Some(piece_type) = self.promotion_piece_type() {
+                    san.push('=');
+                    san.push(san_piece_char(piece_type));
+                }
+                san
+            }
+        };
+        let mut new_board_state = board_state.clone();
+        self.play_move(&mut new_board_state);
+        if let (_, Some(GameEndState::Win(_))) = find_legal_actions(&new_board_state) {
+            san.push('#');
+        } else if side_to_move_in_check(&new_board_state) {
+            san.push('+');
+        }
+        san
+    }
+
+    // extra disambiguation, missing capture, check or promotion symbols, castling
+    // written with zeros and an "e.p." after en passant captures are all accepted
+    pub fn from_san(board_state: &BoardState, san: &str) -> Result<Action, NotationError> {
+        let error = |kind: fn(String) -> NotationError| Err(kind(san.to_string()));
+        let text = san.trim_end_matches(|c| "+#!?".contains(c));
+        let text = text.trim_end_matches("e.p.").trim_end();
+        let legal_actions = find_legal_actions(board_state).0;
+        let castling = match text {
+            "O-O" | "0-0" => Some(true),
+            "O-O-O" | "0-0-0" => Some(false),
+            _ => None,
+        };
+        if let Some(kings_side) = castling {
+            let castling_action = Action::new(ActionType::Castling { kings_side });
+            return if legal_actions.contains(&castling_action) {
+                Ok(castling_action)
+            } else {
+                error(NotationError::IllegalMove)
+            };
+        }
+        if !text.is_ascii() {
+            return error(NotationError::InvalidSyntax);
+        }
+
+        let (piece_type, rest) = match text.chars().next().and_then(san_piece_type) {
+            Some(piece_type) => (piece_type, &text[1..]),
+            None => (PieceType::Pawn, text),
+        };
+        let (rest, promotion) = match rest.find('=') {
+            Some(i) => match san_piece_type_text(&rest[i + 1..]) {
+                Some(promotion) if promotion != PieceType::King => (&rest[..i], Some(promotion)),
+                _ => return error(NotationError::InvalidSyntax),
+            },
+            None if piece_type == PieceType::Pawn && rest.len() > 2 => {
+                match san_piece_type_text(&rest[rest.len() - 1..]) {
+                    Some(promotion) if promotion != PieceType::King => {
+                        (&rest[..rest.len() - 1], Some(promotion))
+                    }
+                    _ => (rest, None),
+                }
+            }
+            None => (rest, None),
+        };
+        if rest.len() < 2 {
+            return error(NotationError::InvalidSyntax);
+        }
+        let (disambiguation, to_text) = rest.split_at(rest.len() - 2);
+        let to = match BoardPosition::try_from_text(to_text) {
+            Some(to) => to,
+            None => return error(NotationError::InvalidSyntax),
+        };
+        let mut from_x = None;
+        let mut from_y = None;
+        for c in disambiguation.chars() {
+            match c {
+                'a'..='h' => from_x = Some(c as u8 - b'a'),
+                '1'..='8' => from_y = Some(c as u8 - b'1'),
+                'x' | '-' | ':' => (),
+                _ => return error(NotationError::InvalidSyntax),
+            }
+        }
+
+        let mut candidates = legal_actions
+            .into_iter()
+            .filter(|action| match action.squares() {
+                Some((from, action_to)) => {
+                    action_to == to
+                        && board_state.get(from).map(|piece| piece.piece_type) == Some(piece_type)
+                        && (from_x.is_none() || from_x == Some(from.x))
+                        && (from_y.is_none() || from_y == Some(from.y))
+                        && action.promotion_piece_type() == promotion
+                }
+                None => false,
+            });
+        match (candidates.next(), candidates.next()) {
+            (Some(action), None) => Ok(action),
+            (Some(_), Some(_)) => error(NotationError::AmbiguousMove),
+            (None, _) => error(NotationError::IllegalMove),
+        }
+    }
+
+    // the long algebraic notation used by uci, like "e2e4", "e1g1" or "e7e8q"
+    pub fn to_uci(&self, board_state: &BoardState) -> String {
+        match self.action_type {
+            ActionType::SimpleMove { from, to } | ActionType::EnPassant { from, to } => {
+                format!("{}{}", from.to_text(), to.to_text())
+            }
+            ActionType::Promotion {
+                from,
+                to,
+                piece_type,
+            } => format!(
+                "{}{}{}",
+                from.to_text(),
+                to.to_text(),
+                // uci always uses lower case piece letters
+                Piece::new(PieceColor::Black, piece_type).to_fen_char()
+            ),
+            ActionType::Castling { kings_side } => {
+                let rank = match board_state.color_turn {
+                    PieceColor::White => 0,
+                    PieceColor::Black => 7,
+                };
+                let king_to = if kings_side {
+                    BoardPosition::new(6, rank)
+                } else {
+                    BoardPosition::new(2, rank)
+                };
+                format!(
+                    "{}{}",
+                    BoardPosition::new(4, rank).to_text(),
+                    king_to.to_text()
+                )
+            }
+        }
+    }
+
+    pub fn from_uci(board_state: &BoardState, uci: &str) -> Result<Action, NotationError> {
+        let valid_syntax = uci.is_ascii()
+            && (uci.len() == 4 || uci.len() == 5)
+            && BoardPosition::try_from_text(&uci[0..2]).is_some()
+            && BoardPosition::try_from_text(&uci[2..4]).is_some()
+            && (uci.len() == 4 || "nbrq".contains(&uci[4..]));
+        if !valid_syntax {
+            return Err(NotationError::InvalidSyntax(uci.to_string()));
+        }
+        find_legal_actions(board_state)
+            .0
+            .into_iter()
+            .find(|action| action.to_uci(board_state) == uci)
+            .ok_or_else(|| NotationError::IllegalMove(uci.to_string()))
+    }
+
+    // the squares the piece moves between, castling is written differently so it has none
+    fn squares(&self) -> Option<(BoardPosition, BoardPosition)> {
+        match self.action_type {
+            ActionType::SimpleMove { from, to }
+            | ActionType::EnPassant { from, to }
+            | ActionType::Promotion {
+                from,
+                to,
+                piece_type: _,
+            } => Some((from, to)),
+            ActionType::Castling { kings_side: _ } => None,
+        }
+    }
+
+    fn promotion_piece_type(&self) -> Option<PieceType> {
+        match self.action_type {
+            ActionType::Promotion {
+                from: _,
+                to: _,
+                piece_type,
+            } => Some(piece_type),
+            _ => None,
+        }
+    }
+}
+
+fn san_piece_char(piece_type: PieceType) -> char {
+    Piece::new(PieceColor::White, piece_type).to_fen_char()
+}
+
+fn san_piece_type(c: char) -> Option<PieceType> {
+    match c {
+        'N' | 'B' | 'R' | 'Q' | 'K' => Piece::try_from_fen_char(c).map(|piece| piece.piece_type),
+        _ => None,
+    }
+}
+
+fn san_piece_type_text(text: &str) -> Option<PieceType> {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => san_piece_type(c),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NotationError {
+    InvalidSyntax(String),
+    IllegalMove(String),
+    AmbiguousMove(String),
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotationError::InvalidSyntax(text) => write!(f, "'{}' is not a valid move", text),
+            NotationError::IllegalMove(text) => write!(f, "'{}' is not a legal move", text),
+            NotationError::AmbiguousMove(text) => {
+                write!(f, "'{}' could be more than one move", text)
+            }
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use super::NotationError;
+use crate::{find_legal_actions, Action, ActionType, BoardPosition, BoardState, PieceType};
+
+// the san of every legal move in the position
+fn all_san(fen: &str) -> Vec<String> {
+    let board_state = BoardState::from_fen(fen).unwrap();
+    find_legal_actions(&board_state)
+        .0
+        .iter()
+        .map(|action| action.to_san(&board_state))
+        .collect()
+}
+
+#[test]
+fn san_disambiguation() {
+    let san = all_san("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1");
+    assert!(san.contains(&"R1a3".to_string()));
+    assert!(san.contains(&"R5a3".to_string()));
+    assert!(san.contains(&"Rb5".to_string()));
+
+    let san = all_san("4k3/8/8/8/8/8/8/1N3NK1 w - - 0 1");
+    assert!(san.contains(&"Nbd2".to_string()));
+    assert!(san.contains(&"Nfd2".to_string()));
+    assert!(san.contains(&"Nh2".to_string()));
+
+    let san = all_san("4k2K/8/8/8/8/Q7/8/Q1Q5 w - - 0 1");
+    assert!(san.contains(&"Qa1b2".to_string()));
+    assert!(san.contains(&"Qcb2".to_string()));
+    assert!(san.contains(&"Q3b2".to_string()));
+}
+
+#[test]
+fn san_special_moves() {
+    let san = all_san("8/4P3/3k4/8/8/8/8/K7 w - - 0 1");
+    assert!(san.contains(&"e8=N+".to_string()));
+    assert!(san.contains(&"e8=Q".to_string()));
+
+    let san = all_san("6k1/5ppp/8/8/8/8/8/R3K2R w KQ - 0 1");
+    assert!(san.contains(&"Ra8#".to_string()));
+    assert!(san.contains(&"O-O".to_string()));
+    assert!(san.contains(&"O-O-O".to_string()));
+
+    let san = all_san("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+    assert!(san.contains(&"exd6".to_string()));
+}
+
+#[test]
+fn san_en_passant_has_no_suffix() {
+    let board_state = BoardState::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+    let en_passant = find_legal_actions(&board_state)
+        .0
+        .into_iter()
+        .find(|action| matches!(action.get_action_type(), ActionType::EnPassant { .. }))
+        .unwrap();
+    assert_eq!(en_passant.to_san(&board_state), "exd6");
+}
+
+#[test]
+fn san_round_trip() {
+    let fens = [
+        crate::STARTING_FEN,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "4k2K/8/8/8/8/Q7/8/Q1Q5 w - - 0 1",
+    ];
+    for fen in fens.iter() {
+        let board_state = BoardState::from_fen(fen).unwrap();
+        for action in find_legal_actions(&board_state).0 {
+            let san = action.to_san(&board_state);
+            assert_eq!(Action::from_san(&board_state, &san), Ok(action), "{}", san);
+        }
+    }
+}
+
+#[test]
+fn san_parsing_is_lenient() {
+    let board_state = BoardState::from_fen("r3k3/1P6/8/8/8/8/8/1N2KN2 w - - 0 1").unwrap();
+    for san in [
+        "Nbd2", "Nb1d2", "Nb1-d2", "Nbd2!?", "bxa8=Q+", "bxa8Q", "ba8=Q",
+    ]
+    .iter()
+    {
+        assert!(Action::from_san(&board_state, san).is_ok(), "{}", san);
+    }
+    let board_state = BoardState::from_fen("4k3/8/8/3pP3/8/8/8/R3K3 w Q d6 0 1").unwrap();
+    for san in ["exd6 e.p.", "exd6e.p.", "ed6", "0-0-0", "O-O-O+"].iter() {
+        assert!(Action::from_san(&board_state, san).is_ok(), "{}", san);
+    }
+}
+
+#[test]
+fn san_errors() {
+    let board_state = BoardState::from_fen("r3k3/1P6/8/8/8/8/8/1N2KN2 w - - 0 1").unwrap();
+    let error = |san: &str| Action::from_san(&board_state, san).unwrap_err();
+    assert_eq!(
+        error("Nd2"),
+        NotationError::AmbiguousMove("Nd2".to_string())
+    );
+    assert_eq!(
+        error("N1d2"),
+        NotationError::AmbiguousMove("N1d2".to_string())
+    );
+    assert_eq!(error("b8"), NotationError::IllegalMove("b8".to_string()));
+    assert_eq!(error("Nc4"), NotationError::IllegalMove("Nc4".to_string()));
+    assert_eq!(error("Qa8"), NotationError::IllegalMove("Qa8".to_string()));
+    assert_eq!(error("O-O"), NotationError::IllegalMove("O-O".to_string()));
+    assert_eq!(
+        error("Ke9"),
+        NotationError::InvalidSyntax("Ke9".to_string())
+    );
+    assert_eq!(
+        error("bxa8=K"),
+        NotationError::InvalidSyntax("bxa8=K".to_string())
+    );
+    assert_eq!(
+        error("Nzd2"),
+        NotationError::InvalidSyntax("Nzd2".to_string())
+    );
+    assert_eq!(error(""), NotationError::InvalidSyntax("".to_string()));
+}
+
+#[test]
+fn uci_conversion() {
+    let board_state = BoardState::from_fen("r3k3/1P6/8/8/8/8/8/1N2K2R w K - 0 1").unwrap();
+    let promotion = Action::new(ActionType::Promotion {
+        from: BoardPosition::from_text("b7"),
+        to: BoardPosition::from_text("a8"),
+        piece_type: PieceType::Knight,
+    });
+    let castling = Action::new(ActionType::Castling { kings_side: true });
+    assert_eq!(promotion.to_uci(&board_state), "b7a8n");
+    assert_eq!(castling.to_uci(&board_state), "e1g1");
+    assert_eq!(Action::from_uci(&board_state, "b7a8n"), Ok(promotion));
+    assert_eq!(Action::from_uci(&board_state, "e1g1"), Ok(castling));
+    for action in find_legal_actions(&board_state).0 {
+        let uci = action.to_uci(&board_state);
+        assert_eq!(Action::from_uci(&board_state, &uci), Ok(action));
+    }
+
+    let error = |uci: &str| Action::from_uci(&board_state, uci).unwrap_err();
+    assert_eq!(
+        error("b7a8"),
+        NotationError::IllegalMove("b7a8".to_string())
+    );
+    assert_eq!(
+        error("e1c1"),
+        NotationError::IllegalMove("e1c1".to_string())
+    );
+    assert_eq!(
+        error("b7a8k"),
+        NotationError::InvalidSyntax("b7a8k".to_string())
+    );
+    assert_eq!(
+        error("e2e9"),
+        NotationError::InvalidSyntax("e2e9".to_string())
+    );
+    assert_eq!(
+        error("Nf3"),
+        NotationError::InvalidSyntax("Nf3".to_string())
+    );
+}
@@ -1,80 +1,211 @@
 mod endgame_table_search;
+mod eval_params;
+mod evaluation_trace;
 mod evaluator;
 mod minimax;
+mod move_ordering;
+mod opening_book;
 mod opening_tables;
+mod pawn_structure;
+mod piece_activity;
+mod polyglot_book;
+mod pruning;
+mod search_limits;
+#[cfg(test)]
+mod search_tests;
+mod transposition_table;
 
-use crate::{Action, BoardState};
-use endgame_table_search::EndgameTableSearcher;
-use evaluator::{Evaluator, Score};
-use minimax::Minimax;
+use crate::BoardState;
+pub use endgame_table_search::EndgameTableSearcher;
+pub use eval_params::{EvalParams, EvalParamsError, SquareTable};
+pub use evaluation_trace::{EvaluationTrace, TraceTerm};
+pub use evaluator::{game_phase, mate_distance, Evaluator, Score, MAX_PHASE};
+pub use minimax::{Minimax, SearchProgress, SearchResult};
+pub use opening_book::{BookMove, OpeningBook, OpeningBookError};
 use opening_tables::OpeningTables;
+pub use opening_tables::DEFAULT_BOOK_DEPTH;
+pub use pawn_structure::PawnStructure;
+pub use piece_activity::{PieceActivity, TermWeights};
+pub use pruning::PruningOptions;
+pub use search_limits::{Clock, SearchLimits, DEFAULT_DEPTH, MAX_DEPTH};
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
 use std::thread;
+use std::time::Duration;
+pub use transposition_table::TranspositionTable;
+pub use transposition_table::DEFAULT_SIZE_MB as DEFAULT_TRANSPOSITION_TABLE_SIZE_MB;
 
 pub struct BestActionFinder {
-    state_receiver: Receiver<State>,
+    message_receiver: Receiver<Message>,
     command_sender: Sender<Command>,
+    // each search gets its own stop signal so stopping one can never stop the next
+    stop_signal: Arc<AtomicBool>,
     state: State,
+    // searches that were cancelled but havent sent their result yet
+    cancelled_searches: u32,
+    // the iterations of the current search that havent been taken yet
+    completed_iterations: Vec<SearchResult>,
 }
 impl BestActionFinder {
     pub fn new() -> BestActionFinder {
         let (command_sender, command_receiver) = channel();
-        let (state_sender, state_receiver) = channel();
-        thread::spawn(move || Self::action_finding_loop(state_sender, command_receiver));
+        let (message_sender, message_receiver) = channel();
+        thread::spawn(move || Self::action_finding_loop(message_sender, command_receiver));
         BestActionFinder {
             state: State::Idle,
             command_sender,
-            state_receiver,
+            stop_signal: Arc::new(AtomicBool::new(false)),
+            message_receiver,
+            cancelled_searches: 0,
+            completed_iterations: Vec::new(),
         }
     }
     pub fn start_finding_move(&mut self, board_state: &BoardState) {
+        self.start_finding_move_with_limits(board_state, SearchLimits::default());
+    }
+    pub fn start_finding_move_with_limits(
+        &mut self,
+        board_state: &BoardState,
+        limits: SearchLimits,
+    ) {
         self.state = State::Thinking(0.0);
+        self.completed_iterations.clear();
+        self.stop_signal = Arc::new(AtomicBool::new(false));
+        self.command_sender
+            .send(Command::FindBestMove(
+                board_state.clone(),
+                limits,
+                self.stop_signal.clone(),
+            ))
+            .unwrap();
+    }
+    pub fn set_transposition_table_size(&self, size_mb: usize) {
         self.command_sender
-            .send(Command::FindBestMove(board_state.clone()))
+            .send(Command::ResizeTranspositionTable(size_mb))
             .unwrap();
     }
+    // scales the piece activity terms of the evaluation, mostly to measure what each is worth
+    pub fn set_term_weights(&self, term_weights: TermWeights) {
+        self.command_sender
+            .send(Command::SetTermWeights(term_weights))
+            .unwrap();
+    }
+    // turns the search's pruning techniques on or off, mostly to measure what each is worth
+    pub fn set_pruning_options(&self, pruning_options: PruningOptions) {
+        self.command_sender
+            .send(Command::SetPruningOptions(pruning_options))
+            .unwrap();
+    }
+    // book moves are only played this many plies into the game, zero turns the book off
+    pub fn set_book_depth(&self, book_depth: u16) {
+        self.command_sender
+            .send(Command::SetBookDepth(book_depth))
+            .unwrap();
+    }
+    // forgets everything learned from previous searches
+    pub fn new_game(&self) {
+        self.command_sender
+            .send(Command::ClearTranspositionTable)
+            .unwrap();
+    }
+    // makes the search finish as soon as possible with the best move it has found so far
+    pub fn stop(&self) {
+        self.stop_signal.store(true, Ordering::Relaxed);
+    }
+    // stops the search and throws away its result so a new search can be started straight away
+    pub fn cancel(&mut self) {
+        if let State::Thinking(_) = self.state {
+            self.stop();
+            self.cancelled_searches += 1;
+            self.state = State::Idle;
+            self.completed_iterations.clear();
+        }
+    }
+    // the iterations the search has completed since this was last called, shallowest first,
+    // they are read from the search by get_state
+    pub fn take_completed_iterations(&mut self) -> Vec<SearchResult> {
+        mem::take(&mut self.completed_iterations)
+    }
     pub fn get_state(&mut self) -> State {
-        match &self.state_receiver.try_recv() {
-            Ok(State::Thinking(progress)) => {
-                self.state = State::Thinking(*progress);
-                self.state
-            }
-            Ok(State::Finished(result)) => {
-                self.state = State::Idle;
-                return State::Finished(*result);
+        // every pending progress update is read so the state never lags behind the search
+        loop {
+            match self.message_receiver.try_recv() {
+                // messages from a cancelled search always come before any from the next search
+                Ok(Message::State(State::Finished(_))) if self.cancelled_searches > 0 => {
+                    self.cancelled_searches -= 1;
+                }
+                Ok(_) if self.cancelled_searches > 0 => (),
+                Ok(Message::Iteration(result)) => self.completed_iterations.push(result),
+                Ok(Message::State(State::Thinking(progress))) => {
+                    self.state = State::Thinking(progress);
+                }
+                Ok(Message::State(State::Finished(result))) => {
+                    self.state = State::Idle;
+                    return State::Finished(result);
+                }
+                Ok(Message::State(State::Idle)) => panic!("best move finder is idle"),
+                Err(TryRecvError::Empty) => return self.state.clone(),
+                Err(TryRecvError::Disconnected) => panic!("move finder thread hung up"),
             }
-            Ok(State::Idle) => panic!("best move finder is idle"),
-            Err(TryRecvError::Empty) => self.state,
-            Err(TryRecvError::Disconnected) => panic!("move finder thread hung up"),
         }
     }
-    fn action_finding_loop(state_sender: Sender<State>, command_receiver: Receiver<Command>) {
-        let evaluator = Evaluator::new(EndgameTableSearcher::new());
-        let opening_tables = OpeningTables::new();
+    fn action_finding_loop(message_sender: Sender<Message>, command_receiver: Receiver<Command>) {
+        let mut evaluator = Evaluator::new(EndgameTableSearcher::new());
+        evaluator.set_params(EvalParams::from_resources());
+        let mut opening_tables = OpeningTables::new();
+        let mut transposition_table = TranspositionTable::new(DEFAULT_TRANSPOSITION_TABLE_SIZE_MB);
+        let mut pruning_options = PruningOptions::default();
         loop {
             let command = command_receiver
                 .recv()
                 .expect("There was an error with the action finding thread.");
             match command {
-                Command::FindBestMove(board_state) => {
-                    let mut update_progress = |progress: f32| {
-                        state_sender.send(State::Thinking(progress)).unwrap();
+                Command::FindBestMove(board_state, mut limits, stop_signal) => {
+                    let mut update_progress = |progress: SearchProgress| {
+                        let message = match progress {
+                            SearchProgress::Fraction(fraction) => {
+                                Message::State(State::Thinking(fraction))
+                            }
+                            SearchProgress::Iteration(result) => Message::Iteration(result.clone()),
+                        };
+                        message_sender.send(message).unwrap();
                     };
                     let action = if let Some(action) = opening_tables.try_find_move(&board_state) {
-                        Ok(action)
+                        Ok(SearchResult {
+                            action,
+                            pv: vec![action],
+                            score: 0,
+                            depth: 0,
+                            nodes: 0,
+                            elapsed: Duration::from_millis(0),
+                        })
                     } else {
-                        let action_result = if evaluator.is_in_endgame(&board_state) {
+                        if evaluator.is_in_endgame(&board_state) {
                             // Depth of one is required because the endgame tables do all the hard work in the endgame
-                            let minimax = Minimax::new(1, &evaluator);
-                            minimax.find_maximising_move(&board_state, &mut update_progress)
-                        } else {
-                            let minimax = Minimax::new(7, &evaluator);
-                            minimax.find_maximising_move(&board_state, &mut update_progress)
-                        };
-                        action_result
+                            limits.max_depth = 1;
+                        }
+                        let mut minimax = Minimax::new(
+                            limits,
+                            &evaluator,
+                            &stop_signal,
+                            &mut transposition_table,
+                        );
+                        minimax.set_pruning_options(pruning_options);
+                        minimax.find_maximising_move(&board_state, &mut update_progress)
                     };
-                    state_sender.send(State::Finished(action)).unwrap();
+                    message_sender
+                        .send(Message::State(State::Finished(action)))
+                        .unwrap();
+                }
+                Command::ResizeTranspositionTable(size_mb) => {
+                    transposition_table = TranspositionTable::new(size_mb);
                 }
+                Command::ClearTranspositionTable => transposition_table.clear(),
+                Command::SetBookDepth(book_depth) => opening_tables.set_book_depth(book_depth),
+                Command::SetTermWeights(term_weights) => evaluator.set_term_weights(term_weights),
+                Command::SetPruningOptions(options) => pruning_options = options,
                 Command::Terminate => {
                     break;
                 }
@@ -89,13 +220,23 @@ impl Drop for BestActionFinder {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub enum State {
     Idle,
     Thinking(f32),
-    Finished(Result<Action, &'static str>),
+    Finished(Result<SearchResult, &'static str>),
+}
+// what the search thread sends back
+enum Message {
+    State(State),
+    Iteration(SearchResult),
 }
 enum Command {
-    FindBestMove(BoardState),
+    FindBestMove(BoardState, SearchLimits, Arc<AtomicBool>),
+    ResizeTranspositionTable(usize),
+    ClearTranspositionTable,
+    SetBookDepth(u16),
+    SetTermWeights(TermWeights),
+    SetPruningOptions(PruningOptions),
     Terminate,
 }
@@ -0,0 +1,413 @@
+#[cfg(test)]
+mod eval_params_tests;
+
+use crate::PieceType;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// tuned parameters are picked up from here when the file exists
+const EVAL_PARAMS_FILE: &str = "eval_params.txt";
+
+// a score for each square written from the side of the piece's own color, so the first
+// row is the far end of the board
+pub type SquareTable = [[i32; 8]; 8];
+
+// every number the evaluation uses, middlegame and endgame pairs are blended by the
+// game phase, they are saved as text so they can be tuned and loaded without
+// rebuilding the engine
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvalParams {
+    pub pawn_value: i32,
+    pub knight_value: i32,
+    pub bishop_value: i32,
+    pub rook_value: i32,
+    pub queen_value: i32,
+    // indexed by piece type in the order pawn, knight, bishop, rook, queen, king
+    pub middlegame_square_tables: [SquareTable; 6],
+    pub endgame_square_tables: [SquareTable; 6],
+    // penalties for each weak pawn
+    pub doubled_pawn_penalty: (i32, i32),
+    pub isolated_pawn_penalty: (i32, i32),
+    pub backward_pawn_penalty: (i32, i32),
+    // bonuses for passed pawns indexed by how many ranks they have moved up the board
+    pub passed_pawn_middlegame_bonus: [i32; 8],
+    pub passed_pawn_endgame_bonus: [i32; 8],
+    // for each square a piece can move to
+    pub knight_mobility: (i32, i32),
+    pub bishop_mobility: (i32, i32),
+    pub rook_mobility: (i32, i32),
+    pub queen_mobility: (i32, i32),
+    // for each pawn in front of the king, only while the king is on its first two ranks
+    pub pawn_shield_bonus: (i32, i32),
+    // indexed by the number of enemy pieces attacking the squares around the king
+    pub king_attackers_penalty: [i32; 8],
+    pub rook_on_open_file_bonus: (i32, i32),
+    pub rook_on_semi_open_file_bonus: (i32, i32),
+    pub bishop_pair_bonus: (i32, i32),
+    pub knight_outpost_bonus: (i32, i32),
+    pub bishop_outpost_bonus: (i32, i32),
+}
+
+impl Default for EvalParams {
+    fn default() -> EvalParams {
+        EvalParams {
+            pawn_value: 100,
+            knight_value: 320,
+            bishop_value: 330,
+            rook_value: 500,
+            queen_value: 900,
+            middlegame_square_tables: [
+                PAWN_SQUARE_TABLE,
+                KNIGHT_SQUARE_TABLE,
+                BISHOP_SQUARE_TABLE,
+                ROOK_SQUARE_TABLE,
+                QUEEN_SQUARE_TABLE,
+                KING_SQUARE_TABLE,
+            ],
+            endgame_square_tables: [
+                PAWN_ENDGAME_SQUARE_TABLE,
+                KNIGHT_SQUARE_TABLE,
+                BISHOP_SQUARE_TABLE,
+                ROOK_SQUARE_TABLE,
+                QUEEN_SQUARE_TABLE,
+                KING_ENDGAME_SQUARE_TABLE,
+            ],
+            doubled_pawn_penalty: (10, 20),
+            isolated_pawn_penalty: (10, 15),
+            backward_pawn_penalty: (8, 10),
+            // they are worth a lot more once there are few pieces left to stop them
+            passed_pawn_middlegame_bonus: [0, 5, 10, 15, 25, 40, 60, 0],
+            passed_pawn_endgame_bonus: [0, 10, 20, 35, 60, 100, 150, 0],
+            knight_mobility: (4, 4),
+            bishop_mobility: (4, 5),
+            rook_mobility: (2, 4),
+            queen_mobility: (1, 2),
+            pawn_shield_bonus: (12, 0),
+            // one attacker is rarely dangerous on its own
+            king_attackers_penalty: [0, 0, 15, 35, 60, 90, 120, 150],
+            rook_on_open_file_bonus: (20, 10),
+            rook_on_semi_open_file_bonus: (10, 5),
+            bishop_pair_bonus: (30, 50),
+            knight_outpost_bonus: (20, 10),
+            bishop_outpost_bonus: (10, 5),
+        }
+    }
+}
+
+impl EvalParams {
+    pub fn piece_value(&self, piece_type: PieceType) -> i32 {
+        match piece_type {
+            PieceType::Pawn => self.pawn_value,
+            PieceType::Knight => self.knight_value,
+            PieceType::Bishop => self.bishop_value,
+            PieceType::Rook => self.rook_value,
+            PieceType::Queen => self.queen_value,
+            PieceType::King => 0,
+        }
+    }
+
+    // the middlegame and endgame square tables for a type of piece
+    pub fn square_tables(&self, piece_type: PieceType) -> (&SquareTable, &SquareTable) {
+        let index = match piece_type {
+            PieceType::Pawn => 0,
+            PieceType::Knight => 1,
+            PieceType::Bishop => 2,
+            PieceType::Rook => 3,
+            PieceType::Queen => 4,
+            PieceType::King => 5,
+        };
+        (
+            &self.middlegame_square_tables[index],
+            &self.endgame_square_tables[index],
+        )
+    }
+
+    // every parameter in the order they are saved, the tuner changes them one at a time
+    pub fn values(&self) -> Vec<i32> {
+        self.clone()
+            .named_values_mut()
+            .into_iter()
+            .flat_map(|(_, values)| values.into_iter().map(|value| *value))
+            .collect()
+    }
+
+    // the opposite of values, there must be one value for every parameter
+    pub fn set_values(&mut self, new_values: &[i32]) {
+        let mut new_values = new_values.iter();
+        for (_, values) in self.named_values_mut() {
+            for value in values {
+                *value = *new_values.next().expect("not enough parameter values");
+            }
+        }
+        assert!(new_values.next().is_none(), "too many parameter values");
+    }
+
+    fn named_values_mut(&mut self) -> Vec<(&'static str, Vec<&mut i32>)> {
+        let [pawn, knight, bishop, rook, queen, king] = &mut self.middlegame_square_tables;
+        let [pawn_end, knight_end, bishop_end, rook_end, queen_end, king_end] =
+            &mut self.endgame_square_tables;
+        vec![
+            ("pawn_value", vec![&mut self.pawn_value]),
+            ("knight_value", vec![&mut self.knight_value]),
+            ("bishop_value", vec![&mut self.bishop_value]),
+            ("rook_value", vec![&mut self.rook_value]),
+            ("queen_value", vec![&mut self.queen_value]),
+            ("pawn_square_table", table(pawn)),
+            ("knight_square_table", table(knight)),
+            ("bishop_square_table", table(bishop)),
+            ("rook_square_table", table(rook)),
+            ("queen_square_table", table(queen)),
+            ("king_square_table", table(king)),
+            ("pawn_endgame_square_table", table(pawn_end)),
+            ("knight_endgame_square_table", table(knight_end)),
+            ("bishop_endgame_square_table", table(bishop_end)),
+            ("rook_endgame_square_table", table(rook_end)),
+            ("queen_endgame_square_table", table(queen_end)),
+            ("king_endgame_square_table", table(king_end)),
+            ("doubled_pawn_penalty", pair(&mut self.doubled_pawn_penalty)),
+            (
+                "isolated_pawn_penalty",
+                pair(&mut self.isolated_pawn_penalty),
+            ),
+            (
+                "backward_pawn_penalty",
+                pair(&mut self.backward_pawn_penalty),
+            ),
+            (
+                "passed_pawn_middlegame_bonus",
+                self.passed_pawn_middlegame_bonus.iter_mut().collect(),
+            ),
+            (
+                "passed_pawn_endgame_bonus",
+                self.passed_pawn_endgame_bonus.iter_mut().collect(),
+            ),
+            ("knight_mobility", pair(&mut self.knight_mobility)),
+            ("bishop_mobility", pair(&mut self.bishop_mobility)),
+            ("rook_mobility", pair(&mut self.rook_mobility)),
+            ("queen_mobility", pair(&mut self.queen_mobility)),
+            ("pawn_shield_bonus", pair(&mut self.pawn_shield_bonus)),
+            (
+                "king_attackers_penalty",
+                self.king_attackers_penalty.iter_mut().collect(),
+            ),
+            (
+                "rook_on_open_file_bonus",
+                pair(&mut self.rook_on_open_file_bonus),
+            ),
+            (
+                "rook_on_semi_open_file_bonus",
+                pair(&mut self.rook_on_semi_open_file_bonus),
+            ),
+            ("bishop_pair_bonus", pair(&mut self.bishop_pair_bonus)),
+            ("knight_outpost_bonus", pair(&mut self.knight_outpost_bonus)),
+            ("bishop_outpost_bonus", pair(&mut self.bishop_outpost_bonus)),
+        ]
+    }
+
+    // each parameter is its name followed by its values, square tables are written with
+    // a row per line
+    pub fn to_text(&self) -> String {
+        let mut params = self.clone();
+        let mut text = String::new();
+        for (name, values) in params.named_values_mut() {
+            let values: Vec<String> = values.into_iter().map(|value| value.to_string()).collect();
+            if values.len() == 64 {
+                text.push_str(name);
+                text.push('\n');
+                for row in values.chunks(8) {
+                    text.push_str(&format!("    {}\n", row.join(" ")));
+                }
+            } else {
+                text.push_str(&format!("{} {}\n", name, values.join(" ")));
+            }
+        }
+        text
+    }
+
+    // everything after a # on a line is a comment, parameters left out of the text keep
+    // their default values
+    pub fn parse(text: &str) -> Result<EvalParams, EvalParamsError> {
+        let tokens: Vec<&str> = text
+            .lines()
+            .flat_map(|line| line.split('#').next().unwrap_or("").split_whitespace())
+            .collect();
+        let mut params = EvalParams::default();
+        let mut named_values = params.named_values_mut();
+        let mut i = 0;
+        while i < tokens.len() {
+            let name = tokens[i];
+            let values = match named_values.iter_mut().find(|(other, _)| *other == name) {
+                Some((_, values)) => values,
+                None => return Err(EvalParamsError::UnknownParam(name.to_string())),
+            };
+            i += 1;
+            let count = tokens[i..]
+                .iter()
+                .take_while(|token| token.parse::<i32>().is_ok())
+                .count();
+            if count != values.len() {
+                return Err(EvalParamsError::WrongValueCount(
+                    name.to_string(),
+                    values.len(),
+                    count,
+                ));
+            }
+            for (value, token) in values.iter_mut().zip(tokens[i..i + count].iter()) {
+                **value = token.parse().unwrap();
+            }
+            i += count;
+        }
+        drop(named_values);
+        Ok(params)
+    }
+
+    pub fn load(path: &Path) -> Result<EvalParams, EvalParamsError> {
+        EvalParams::parse(&fs::read_to_string(path).map_err(EvalParamsError::Io)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), EvalParamsError> {
+        fs::write(path, self.to_text()).map_err(EvalParamsError::Io)
+    }
+
+    // the tuned parameters from the resources directory, or the defaults without them
+    pub fn from_resources() -> EvalParams {
+        let resource_dir = if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
+            let mut path = PathBuf::from(manifest_dir);
+            path.push("resources");
+            path
+        } else {
+            PathBuf::from("./resources")
+        };
+        let path = resource_dir.join(EVAL_PARAMS_FILE);
+        if !path.exists() {
+            return EvalParams::default();
+        }
+        match EvalParams::load(&path) {
+            Ok(params) => params,
+            Err(err) => {
+                eprintln!(
+                    "could not load evaluation parameters {}: {}",
+                    path.display(),
+                    err
+                );
+                EvalParams::default()
+            }
+        }
+    }
+}
+
+fn pair(pair: &mut (i32, i32)) -> Vec<&mut i32> {
+    vec![&mut pair.0, &mut pair.1]
+}
+
+fn table(table: &mut SquareTable) -> Vec<&mut i32> {
+    table.iter_mut().flatten().collect()
+}
+
+#[derive(Debug)]
+pub enum EvalParamsError {
+    Io(io::Error),
+    UnknownParam(String),
+    // the name of the parameter, how many values it has and how many were given
+    WrongValueCount(String, usize, usize),
+}
+
+impl fmt::Display for EvalParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalParamsError::Io(err) => write!(f, "{}", err),
+            EvalParamsError::UnknownParam(name) => write!(f, "unknown parameter '{}'", name),
+            EvalParamsError::WrongValueCount(name, expected, found) => write!(
+                f,
+                "{} should have {} values but has {}",
+                name, expected, found
+            ),
+        }
+    }
+}
+
+const PAWN_SQUARE_TABLE: SquareTable = [
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [50, 50, 50, 50, 50, 50, 50, 50],
+    [10, 10, 20, 30, 30, 20, 10, 10],
+    [5, 5, 10, 25, 25, 10, 5, 5],
+    [0, 0, 0, 20, 20, 0, 0, 0],
+    [5, -5, -10, 0, 0, -10, -5, 5],
+    [5, 10, 10, -20, -20, 10, 10, 5],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+];
+// pawns are worth more the closer they get to promoting
+const PAWN_ENDGAME_SQUARE_TABLE: SquareTable = [
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [80, 80, 80, 80, 80, 80, 80, 80],
+    [50, 50, 50, 50, 50, 50, 50, 50],
+    [30, 30, 30, 30, 30, 30, 30, 30],
+    [15, 15, 15, 15, 15, 15, 15, 15],
+    [5, 5, 5, 5, 5, 5, 5, 5],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+];
+const KNIGHT_SQUARE_TABLE: SquareTable = [
+    [-50, -40, -30, -30, -30, -30, -40, -50],
+    [-40, -20, 0, 0, 0, 0, -20, -40],
+    [-30, 0, 10, 15, 15, 10, 0, -30],
+    [-30, 5, 15, 20, 20, 15, 5, -30],
+    [-30, 0, 15, 20, 20, 15, 0, -30],
+    [-30, 5, 10, 15, 15, 10, 5, -30],
+    [-40, -20, 0, 5, 5, 0, -20, -40],
+    [-50, -40, -30, -30, -30, -30, -40, -50],
+];
+const BISHOP_SQUARE_TABLE: SquareTable = [
+    [-20, -10, -10, -10, -10, -10, -10, -20],
+    [-10, 0, 0, 0, 0, 0, 0, -10],
+    [-10, 0, 5, 10, 10, 5, 0, -10],
+    [-10, 5, 5, 10, 10, 5, 5, -10],
+    [-10, 0, 10, 10, 10, 10, 0, -10],
+    [-10, 10, 10, 10, 10, 10, 10, -10],
+    [-10, 5, 0, 0, 0, 0, 5, -10],
+    [-20, -10, -10, -10, -10, -10, -10, -20],
+];
+const ROOK_SQUARE_TABLE: SquareTable = [
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [5, 10, 10, 10, 10, 10, 10, 5],
+    [-5, 0, 0, 0, 0, 0, 0, -5],
+    [-5, 0, 0, 0, 0, 0, 0, -5],
+    [-5, 0, 0, 0, 0, 0, 0, -5],
+    [-5, 0, 0, 0, 0, 0, 0, -5],
+    [-5, 0, 0, 0, 0, 0, 0, -5],
+    [0, 0, 0, 5, 5, 0, 0, 0],
+];
+const QUEEN_SQUARE_TABLE: SquareTable = [
+    [-20, -10, -10, -5, -5, -10, -10, -20],
+    [-10, 0, 0, 0, 0, 0, 0, -10],
+    [-10, 0, 5, 5, 5, 5, 0, -10],
+    [-5, 0, 5, 5, 5, 5, 0, -5],
+    [0, 0, 5, 5, 5, 5, 0, -5],
+    [-10, 5, 5, 5, 5, 5, 0, -10],
+    [-10, 0, 5, 0, 0, 0, 0, -10],
+    [-20, -10, -10, -5, -5, -10, -10, -20],
+];
+const KING_SQUARE_TABLE: SquareTable = [
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-20, -30, -30, -40, -40, -30, -30, -20],
+    [-10, -20, -20, -20, -20, -20, -20, -10],
+    [20, 20, 0, 0, 0, 0, 20, 20],
+    [20, 30, 10, 0, 0, 10, 30, 20],
+];
+// with few pieces left the king is safe in the centre and needed to support pawns
+const KING_ENDGAME_SQUARE_TABLE: SquareTable = [
+    [-50, -40, -30, -20, -20, -30, -40, -50],
+    [-30, -20, -10, 0, 0, -10, -20, -30],
+    [-30, -10, 20, 30, 30, 20, -10, -30],
+    [-30, -10, 30, 40, 40, 30, -10, -30],
+    [-30, -10, 30, 40, 40, 30, -10, -30],
+    [-30, -10, 20, 30, 30, 20, -10, -30],
+    [-30, -30, 0, 0, 0, 0, -30, -30],
+    [-50, -30, -30, -30, -30, -30, -30, -50],
+];
@@ -0,0 +1,72 @@
+use super::{EvalParams, EvalParamsError};
+use crate::{BoardState, EndgameTableSearcher, Evaluator, PieceType};
+
+#[test]
+fn text_round_trip() {
+    let mut params = EvalParams {
+        knight_value: 310,
+        ..EvalParams::default()
+    };
+    params.endgame_square_tables[0][1][2] = -7;
+    params.king_attackers_penalty[3] = 40;
+    let text = params.to_text();
+    assert!(text.starts_with("pawn_value 100\nknight_value 310\n"));
+    assert!(text.contains("\nbishop_pair_bonus 30 50\n"));
+    assert_eq!(EvalParams::parse(&text).unwrap(), params);
+}
+
+#[test]
+fn values_round_trip() {
+    let params = EvalParams::default();
+    let mut values = params.values();
+    // 5 piece values, 12 square tables and 50 values for the other terms
+    assert_eq!(values.len(), 5 + 12 * 64 + 50);
+    assert_eq!(values[1], 320);
+    values[1] = 300;
+    let mut changed = params.clone();
+    changed.set_values(&values);
+    assert_eq!(changed.piece_value(PieceType::Knight), 300);
+    assert_eq!(changed.values(), values);
+}
+
+#[test]
+fn parse_keeps_missing_defaults() {
+    let params =
+        EvalParams::parse("# only the rooks\nrook_value 480\nrook_mobility 3 5 # per square\n")
+            .unwrap();
+    let expected = EvalParams {
+        rook_value: 480,
+        rook_mobility: (3, 5),
+        ..EvalParams::default()
+    };
+    assert_eq!(params, expected);
+}
+
+#[test]
+fn parse_errors() {
+    assert!(matches!(
+        EvalParams::parse("pawn_value 100\nknight_valeu 300\n"),
+        Err(EvalParamsError::UnknownParam(name)) if name == "knight_valeu"
+    ));
+    assert!(matches!(
+        EvalParams::parse("doubled_pawn_penalty 10\n"),
+        Err(EvalParamsError::WrongValueCount(_, 2, 1))
+    ));
+    assert!(matches!(
+        EvalParams::parse("queen_value nine\n"),
+        Err(EvalParamsError::WrongValueCount(_, 1, 0))
+    ));
+}
+
+#[test]
+fn evaluator_uses_params() {
+    let mut evaluator = Evaluator::new(EndgameTableSearcher::new());
+    // black has a knight against white's six pawns
+    let board_state = BoardState::from_fen("4k3/8/8/3n4/8/8/PPP2PPP/4K3 w - - 0 1").unwrap();
+    let before = evaluator.full_evaluate(&board_state);
+    let mut params = EvalParams::default();
+    params.knight_value += 50;
+    evaluator.set_params(params);
+    assert_eq!(evaluator.full_evaluate(&board_state), before + 50);
+    assert_eq!(evaluator.piece_value(PieceType::Knight), 370);
+}
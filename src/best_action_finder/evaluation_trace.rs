@@ -0,0 +1,72 @@
+use super::evaluator::{Score, MAX_PHASE};
+use crate::PieceColor;
+use std::fmt;
+
+// one term of the evaluation as middlegame and endgame scores for each color, indexed
+// by color, higher is better for that color
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TraceTerm {
+    pub name: &'static str,
+    pub scores: [(i32, i32); 2],
+}
+
+impl TraceTerm {
+    pub fn new(name: &'static str, scores: [(i32, i32); 2]) -> TraceTerm {
+        TraceTerm { name, scores }
+    }
+
+    // the score of the color blended by the game phase
+    pub fn tapered(&self, color: PieceColor, phase: i32) -> i32 {
+        let (middlegame, endgame) = self.scores[color as usize];
+        (middlegame * phase + endgame * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+
+// everything that went into an evaluation, the score is the same one full_evaluate
+// gives so it is from black's side, and when the endgame tables know the position
+// their score replaces the terms
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvaluationTrace {
+    pub phase: i32,
+    pub terms: Vec<TraceTerm>,
+    pub endgame_table_score: Option<Score>,
+    pub score: Score,
+}
+
+impl EvaluationTrace {
+    pub fn term(&self, name: &str) -> Option<&TraceTerm> {
+        self.terms.iter().find(|term| term.name == name)
+    }
+}
+
+// a table of the terms in centipawns, the total column and the final score are from
+// white's side as that is how people usually read them
+impl fmt::Display for EvaluationTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<16}{:>10}{:>10}{:>10}{:>10}{:>10}",
+            "term", "white mg", "white eg", "black mg", "black eg", "total"
+        )?;
+        for term in self.terms.iter() {
+            let white = term.scores[PieceColor::White as usize];
+            let black = term.scores[PieceColor::Black as usize];
+            let total = term.tapered(PieceColor::White, self.phase)
+                - term.tapered(PieceColor::Black, self.phase);
+            writeln!(
+                f,
+                "{:<16}{:>10}{:>10}{:>10}{:>10}{:>10}",
+                term.name, white.0, white.1, black.0, black.1, total
+            )?;
+        }
+        writeln!(f, "phase: {} of {}", self.phase, MAX_PHASE)?;
+        if let Some(score) = self.endgame_table_score {
+            writeln!(
+                f,
+                "endgame tables: {} (replaces the terms)",
+                -(score as i32)
+            )?;
+        }
+        write!(f, "score: {} (from white's side)", -(self.score as i32))
+    }
+}
@@ -1,88 +1,62 @@
+#[cfg(test)]
+mod evaluator_tests;
+
+use super::eval_params::EvalParams;
+use super::evaluation_trace::{EvaluationTrace, TraceTerm};
+use super::pawn_structure::{PawnHashTable, PawnStructure};
+use super::piece_activity::{PieceActivity, TermWeights};
 use super::EndgameTableSearcher;
 use crate::{find_legal_actions, BoardPosition, BoardState, GameEndState, PieceColor, PieceType};
+use std::cell::RefCell;
 
-const PAWN_VALUE: Score = 100;
-const PAWN_SQUARE_TABLE: [[Score; 8]; 8] = [
-    [0, 0, 0, 0, 0, 0, 0, 0],
-    [50, 50, 50, 50, 50, 50, 50, 50],
-    [10, 10, 20, 30, 30, 20, 10, 10],
-    [5, 5, 10, 25, 25, 10, 5, 5],
-    [0, 0, 0, 20, 20, 0, 0, 0],
-    [5, -5, -10, 0, 0, -10, -5, 5],
-    [5, 10, 10, -20, -20, 10, 10, 5],
-    [0, 0, 0, 0, 0, 0, 0, 0],
-];
+// middlegame and endgame scores indexed by color
+type ColorScores = [(i32, i32); 2];
 
-const KNIGHT_VALUE: Score = 320;
-const KNIGHT_SQUARE_TABLE: [[Score; 8]; 8] = [
-    [-50, -40, -30, -30, -30, -30, -40, -50],
-    [-40, -20, 0, 0, 0, 0, -20, -40],
-    [-30, 0, 10, 15, 15, 10, 0, -30],
-    [-30, 5, 15, 20, 20, 15, 5, -30],
-    [-30, 0, 15, 20, 20, 15, 0, -30],
-    [-30, 5, 10, 15, 15, 10, 5, -30],
-    [-40, -20, 0, 5, 5, 0, -20, -40],
-    [-50, -40, -30, -30, -30, -30, -40, -50],
-];
-const BISHOP_VALUE: Score = 330;
-const BISHOP_SQUARE_TABLE: [[Score; 8]; 8] = [
-    [-20, -10, -10, -10, -10, -10, -10, -20],
-    [-10, 0, 0, 0, 0, 0, 0, -10],
-    [-10, 0, 5, 10, 10, 5, 0, -10],
-    [-10, 5, 5, 10, 10, 5, 5, -10],
-    [-10, 0, 10, 10, 10, 10, 0, -10],
-    [-10, 10, 10, 10, 10, 10, 10, -10],
-    [-10, 5, 0, 0, 0, 0, 5, -10],
-    [-20, -10, -10, -10, -10, -10, -10, -20],
-];
-const ROOK_VALUE: Score = 500;
-const ROOK_SQUARE_TABLE: [[Score; 8]; 8] = [
-    [0, 0, 0, 0, 0, 0, 0, 0],
-    [5, 10, 10, 10, 10, 10, 10, 5],
-    [-5, 0, 0, 0, 0, 0, 0, -5],
-    [-5, 0, 0, 0, 0, 0, 0, -5],
-    [-5, 0, 0, 0, 0, 0, 0, -5],
-    [-5, 0, 0, 0, 0, 0, 0, -5],
-    [-5, 0, 0, 0, 0, 0, 0, -5],
-    [0, 0, 0, 5, 5, 0, 0, 0],
-];
-const QUEEN_VALUE: Score = 900;
-const QUEEN_SQUARE_TABLE: [[Score; 8]; 8] = [
-    [-20, -10, -10, -5, -5, -10, -10, -20],
-    [-10, 0, 0, 0, 0, 0, 0, -10],
-    [-10, 0, 5, 5, 5, 5, 0, -10],
-    [-5, 0, 5, 5, 5, 5, 0, -5],
-    [0, 0, 5, 5, 5, 5, 0, -5],
-    [-10, 5, 5, 5, 5, 5, 0, -10],
-    [-10, 0, 5, 0, 0, 0, 0, -10],
-    [-20, -10, -10, -5, -5, -10, -10, -20],
-];
-const KING_SQUARE_TABLE: [[Score; 8]; 8] = [
-    [-30, -40, -40, -50, -50, -40, -40, -30],
-    [-30, -40, -40, -50, -50, -40, -40, -30],
-    [-30, -40, -40, -50, -50, -40, -40, -30],
-    [-30, -40, -40, -50, -50, -40, -40, -30],
-    [-20, -30, -30, -40, -40, -30, -30, -20],
-    [-10, -20, -20, -20, -20, -20, -20, -10],
-    [20, 20, 0, 0, 0, 0, 20, 20],
-    [20, 30, 10, 0, 0, 10, 30, 20],
-];
+// the game phase counts down from this with the starting material to 0 with none, each
+// minor piece is worth 1, each rook 2 and each queen 4
+pub const MAX_PHASE: i32 = 24;
+const KNIGHT_PHASE: i32 = 1;
+const BISHOP_PHASE: i32 = 1;
+const ROOK_PHASE: i32 = 2;
+const QUEEN_PHASE: i32 = 4;
 
 pub type Score = i16;
 
+// evaluations are kept below the scores used for checkmates, however the parameters are
+// tuned a position can't look like a forced mate
+const MAX_EVALUATION: i32 = Score::MAX as i32 - u8::MAX as i32 - 1;
+
 pub struct Evaluator {
     endgame_tables: EndgameTableSearcher,
+    // the evaluator is only used by one search at a time so the cache can be shared
+    pawn_hash_table: RefCell<PawnHashTable>,
+    params: EvalParams,
+    term_weights: TermWeights,
 }
 
 impl Evaluator {
     pub fn new(endgame_tables: EndgameTableSearcher) -> Evaluator {
-        Evaluator { endgame_tables }
+        Evaluator {
+            endgame_tables,
+            pawn_hash_table: RefCell::new(PawnHashTable::new()),
+            params: EvalParams::default(),
+            term_weights: TermWeights::default(),
+        }
+    }
+    pub fn params(&self) -> &EvalParams {
+        &self.params
+    }
+    pub fn set_params(&mut self, params: EvalParams) {
+        self.params = params;
+        // the cached pawn scores were worked out with the old parameters
+        self.pawn_hash_table.borrow_mut().clear();
+    }
+    pub fn set_term_weights(&mut self, term_weights: TermWeights) {
+        self.term_weights = term_weights;
     }
 
     pub fn quick_evaluate(&self, board_state: &BoardState) -> Score {
-        let mut score = 0;
-        score += self.square_tables(board_state);
-        score
+        taper(board_state, self.square_tables(board_state))
     }
     pub fn full_evaluate(&self, board_state: &BoardState) -> Score {
         // TODO: check for checkmates
@@ -90,10 +64,63 @@ impl Evaluator {
             Some(score) => return score,
             None => (),
         }
-
-        let mut score = 0;
-        score += self.square_tables(board_state);
-        score
+        self.evaluate_terms(board_state)
+    }
+    // the full evaluation without the endgame tables, which are the same whatever the
+    // parameters are so the tuner leaves them out
+    pub fn evaluate_terms(&self, board_state: &BoardState) -> Score {
+        let (mut middlegame, mut endgame) = self.square_tables(board_state);
+        let (pawn_middlegame, pawn_endgame) = self
+            .pawn_hash_table
+            .borrow_mut()
+            .score(board_state, &self.params);
+        let (activity_middlegame, activity_endgame) =
+            PieceActivity::new(board_state, &self.params).score(&self.term_weights);
+        middlegame += pawn_middlegame + activity_middlegame;
+        endgame += pawn_endgame + activity_endgame;
+        taper(board_state, (middlegame, endgame))
+    }
+    // the full evaluation split into its terms for each color, used to see why the
+    // engine likes or dislikes a position
+    pub fn trace(&self, board_state: &BoardState) -> EvaluationTrace {
+        let (material, squares) = self.material_and_squares(board_state);
+        let pawn_structure = PawnStructure::new(board_state);
+        let activity_terms =
+            PieceActivity::new(board_state, &self.params).weighted_terms(&self.term_weights);
+        let mut terms = vec![
+            TraceTerm::new("material", material),
+            TraceTerm::new("piece squares", squares),
+        ];
+        let pawn_names = [
+            "doubled pawns",
+            "isolated pawns",
+            "backward pawns",
+            "passed pawns",
+        ];
+        let white_pawn_terms = pawn_structure.terms(PieceColor::White, &self.params);
+        let black_pawn_terms = pawn_structure.terms(PieceColor::Black, &self.params);
+        for (i, name) in pawn_names.iter().enumerate() {
+            terms.push(TraceTerm::new(
+                name,
+                [white_pawn_terms[i], black_pawn_terms[i]],
+            ));
+        }
+        let activity_names = [
+            "mobility",
+            "king safety",
+            "rook files",
+            "bishop pair",
+            "outposts",
+        ];
+        for (name, scores) in activity_names.iter().zip(activity_terms.iter()) {
+            terms.push(TraceTerm::new(name, *scores));
+        }
+        EvaluationTrace {
+            phase: game_phase(board_state),
+            terms,
+            endgame_table_score: self.check_endgame_tables(board_state),
+            score: self.full_evaluate(board_state),
+        }
     }
     pub fn score_for_checkmate(&self, color: PieceColor, moves_until: u8) -> Score {
         match color {
@@ -101,23 +128,21 @@ impl Evaluator {
             PieceColor::Black => Score::MAX - moves_until as i16,
         }
     }
+    pub fn piece_value(&self, piece_type: PieceType) -> Score {
+        self.params.piece_value(piece_type) as Score
+    }
+    pub fn is_checkmate_score(&self, score: Score) -> bool {
+        mate_distance(score).is_some()
+    }
     pub fn is_in_endgame(&self, board_state: &BoardState) -> bool {
         self.endgame_tables.win_loss_check(board_state).is_some()
     }
     fn count_pieces(&self, board_state: &BoardState) -> u8 {
-        let mut count = 0;
-        for y in 0..8 {
-            for x in 0..8 {
-                if board_state.get(BoardPosition::new(x, y)).is_some() {
-                    count += 1;
-                }
-            }
-        }
-        count
+        board_state.occupied_bitboard().count_ones() as u8
     }
     fn check_endgame_tables(&self, board_state: &BoardState) -> Option<Score> {
         if self.count_pieces(board_state) < 6 {
-            let (_, game_end_state) = find_legal_actions(board_state, false);
+            let (_, game_end_state) = find_legal_actions(board_state);
             match game_end_state {
                 Some(GameEndState::Draw) => Some(0),
                 Some(GameEndState::Win(color)) => Some(self.score_for_checkmate(color, 0)),
@@ -130,62 +155,76 @@ impl Evaluator {
             None
         }
     }
-    fn square_tables(&self, board_state: &BoardState) -> Score {
-        let mut score = 0;
+    // material and piece positions as middlegame and endgame scores
+    fn square_tables(&self, board_state: &BoardState) -> (i32, i32) {
+        let (material, squares) = self.material_and_squares(board_state);
+        let white = PieceColor::White as usize;
+        let black = PieceColor::Black as usize;
+        (
+            material[black].0 + squares[black].0 - material[white].0 - squares[white].0,
+            material[black].1 + squares[black].1 - material[white].1 - squares[white].1,
+        )
+    }
+    // the material and square table scores of each color, higher is better for that color
+    fn material_and_squares(&self, board_state: &BoardState) -> (ColorScores, ColorScores) {
+        let mut material = [(0, 0); 2];
+        let mut squares = [(0, 0); 2];
         for y in 0..8 {
             for x in 0..8 {
-                let piece_option = board_state.get(BoardPosition::new(x, y));
-                if let Some(piece) = piece_option {
-                    match piece.color {
-                        PieceColor::White => match piece.piece_type {
-                            PieceType::Pawn => {
-                                score -= PAWN_VALUE
-                                    + PAWN_SQUARE_TABLE[inverse_axis(y) as usize][x as usize]
-                            }
-                            PieceType::Knight => {
-                                score -= KNIGHT_VALUE
-                                    + KNIGHT_SQUARE_TABLE[inverse_axis(y) as usize][x as usize]
-                            }
-                            PieceType::Bishop => {
-                                score -= BISHOP_VALUE
-                                    + BISHOP_SQUARE_TABLE[inverse_axis(y) as usize][x as usize]
-                            }
-                            PieceType::Rook => {
-                                score -= ROOK_VALUE
-                                    + ROOK_SQUARE_TABLE[inverse_axis(y) as usize][x as usize]
-                            }
-                            PieceType::Queen => {
-                                score -= QUEEN_VALUE
-                                    + QUEEN_SQUARE_TABLE[inverse_axis(y) as usize][x as usize]
-                            }
-                            PieceType::King => {
-                                score -= KING_SQUARE_TABLE[inverse_axis(y) as usize][x as usize]
-                            }
-                        },
-                        PieceColor::Black => match piece.piece_type {
-                            PieceType::Pawn => {
-                                score += PAWN_VALUE + PAWN_SQUARE_TABLE[y as usize][x as usize]
-                            }
-                            PieceType::Knight => {
-                                score += KNIGHT_VALUE + KNIGHT_SQUARE_TABLE[y as usize][x as usize]
-                            }
-                            PieceType::Bishop => {
-                                score += BISHOP_VALUE + BISHOP_SQUARE_TABLE[y as usize][x as usize]
-                            }
-                            PieceType::Rook => {
-                                score += ROOK_VALUE + ROOK_SQUARE_TABLE[y as usize][x as usize]
-                            }
-                            PieceType::Queen => {
-                                score += QUEEN_VALUE + QUEEN_SQUARE_TABLE[y as usize][x as usize]
-                            }
-                            PieceType::King => score += KING_SQUARE_TABLE[y as usize][x as usize],
-                        },
-                    }
+                if let Some(piece) = board_state.get(BoardPosition::new(x, y)) {
+                    // the tables are written from the side of the piece's own color
+                    let row = match piece.color {
+                        PieceColor::White => inverse_axis(y) as usize,
+                        PieceColor::Black => y as usize,
+                    };
+                    let index = piece.color as usize;
+                    let value = self.params.piece_value(piece.piece_type);
+                    let (middlegame_table, endgame_table) =
+                        self.params.square_tables(piece.piece_type);
+                    material[index].0 += value;
+                    material[index].1 += value;
+                    squares[index].0 += middlegame_table[row][x as usize];
+                    squares[index].1 += endgame_table[row][x as usize];
                 }
             }
         }
-        score
+        (material, squares)
+    }
+}
+
+// blends the middlegame and endgame scores by the game phase
+// the color giving checkmate and how many plies away it is when the score is for a forced
+// mate, checkmate scores count in from the ends of the score range
+pub fn mate_distance(score: Score) -> Option<(PieceColor, u8)> {
+    let black_distance = Score::MAX as i32 - score as i32;
+    let white_distance = score as i32 - Score::MIN as i32;
+    if black_distance <= u8::MAX as i32 {
+        Some((PieceColor::Black, black_distance as u8))
+    } else if white_distance <= u8::MAX as i32 {
+        Some((PieceColor::White, white_distance as u8))
+    } else {
+        None
+    }
+}
+
+fn taper(board_state: &BoardState, (middlegame, endgame): (i32, i32)) -> Score {
+    let phase = game_phase(board_state);
+    let score = (middlegame * phase + endgame * (MAX_PHASE - phase)) / MAX_PHASE;
+    score.clamp(-MAX_EVALUATION, MAX_EVALUATION) as Score
+}
+
+// how much of the middlegame is left, from MAX_PHASE with all the pieces on the board
+// down to 0 when only kings and pawns remain, promoted pieces can't take it above the max
+pub fn game_phase(board_state: &BoardState) -> i32 {
+    let mut phase = 0;
+    for color in [PieceColor::White, PieceColor::Black].iter() {
+        let count = |piece_type| board_state.piece_bitboard(*color, piece_type).count_ones() as i32;
+        phase += count(PieceType::Knight) * KNIGHT_PHASE
+            + count(PieceType::Bishop) * BISHOP_PHASE
+            + count(PieceType::Rook) * ROOK_PHASE
+            + count(PieceType::Queen) * QUEEN_PHASE;
     }
+    phase.min(MAX_PHASE)
 }
 
 fn inverse_axis(n: u8) -> u8 {
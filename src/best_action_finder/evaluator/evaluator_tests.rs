@@ -0,0 +1,130 @@
+use super::{game_phase, mate_distance, taper, Evaluator, Score, MAX_EVALUATION, MAX_PHASE};
+use crate::{BoardState, EndgameTableSearcher, PieceColor};
+
+fn phase(fen: &str) -> i32 {
+    game_phase(&BoardState::from_fen(fen).unwrap())
+}
+
+#[test]
+fn game_phase_from_material() {
+    assert_eq!(phase(crate::STARTING_FEN), MAX_PHASE);
+    assert_eq!(phase("4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 w - - 0 1"), 0);
+    assert_eq!(phase("r3k3/8/8/8/8/8/8/4KB2 w - - 0 1"), 3);
+    assert_eq!(phase("3qk3/8/8/8/8/8/8/2Q1K3 w - - 0 1"), 8);
+    // extra queens from promotions don't go past the starting material
+    assert_eq!(
+        phase("QQQQkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQk - 0 1"),
+        MAX_PHASE
+    );
+}
+
+#[test]
+fn king_table_depends_on_phase() {
+    let evaluator = Evaluator::new(EndgameTableSearcher::new());
+    let score = |fen: &str| evaluator.quick_evaluate(&BoardState::from_fen(fen).unwrap());
+    // scores are from black's side so white is better off with the lower score
+    assert!(
+        score("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQ1RK1 w kq - 0 1")
+            < score("rnbqkbnr/pppppppp/8/8/4K3/8/PPPPPPPP/RNBQ1R2 w kq - 0 1")
+    );
+    assert!(
+        score("4k3/ppp5/8/8/4K3/8/PPP5/8 w - - 0 1") < score("4k3/ppp5/8/8/8/8/PPP5/7K w - - 0 1")
+    );
+}
+
+#[test]
+fn advanced_pawns_worth_more_in_endgame() {
+    let evaluator = Evaluator::new(EndgameTableSearcher::new());
+    let score = |fen: &str| evaluator.quick_evaluate(&BoardState::from_fen(fen).unwrap());
+    let endgame_gain =
+        score("4k3/8/8/8/8/P7/7P/4K3 w - - 0 1") - score("4k3/8/P7/8/8/8/7P/4K3 w - - 0 1");
+    let middlegame_gain = score("rnbqkbnr/8/8/8/8/P7/7P/RNBQKBNR w KQkq - 0 1")
+        - score("rnbqkbnr/8/P7/8/8/8/7P/RNBQKBNR w KQkq - 0 1");
+    assert!(endgame_gain > middlegame_gain);
+    assert!(middlegame_gain > 0);
+}
+
+#[test]
+fn trace_adds_up_to_full_evaluation() {
+    let evaluator = Evaluator::new(EndgameTableSearcher::new());
+    let fens = [
+        crate::STARTING_FEN,
+        "r1bqk2r/pp2bppp/2n1pn2/3p4/2PP4/2N2N2/PP3PPP/R1BQKB1R w KQkq - 0 8",
+        "8/5pk1/6p1/3P4/8/6P1/5PK1/2R5 b - - 0 40",
+    ];
+    for fen in fens.iter() {
+        let board_state = BoardState::from_fen(fen).unwrap();
+        let trace = evaluator.trace(&board_state);
+        assert_eq!(trace.score, evaluator.full_evaluate(&board_state));
+        assert_eq!(trace.endgame_table_score, None);
+        let sum = |color: PieceColor| {
+            trace
+                .terms
+                .iter()
+                .fold((0, 0), |(middlegame, endgame), term| {
+                    let score = term.scores[color as usize];
+                    (middlegame + score.0, endgame + score.1)
+                })
+        };
+        let (white, black) = (sum(PieceColor::White), sum(PieceColor::Black));
+        let middlegame = black.0 - white.0;
+        let endgame = black.1 - white.1;
+        let tapered = (middlegame * trace.phase + endgame * (MAX_PHASE - trace.phase)) / MAX_PHASE;
+        assert_eq!(tapered as i16, trace.score, "{}", fen);
+    }
+}
+
+#[test]
+fn trace_terms_per_color() {
+    let evaluator = Evaluator::new(EndgameTableSearcher::new());
+    let trace = evaluator.trace(&BoardState::default());
+    for term in trace.terms.iter() {
+        assert_eq!(term.scores[0], term.scores[1], "{}", term.name);
+    }
+    // the d pawn has taken on c3, doubling the white c pawns
+    let trace = evaluator.trace(
+        &BoardState::from_fen("rnbqkbnr/pppppppp/8/8/8/2P5/PPP1PPPP/RNBQKBNR w KQkq - 0 1")
+            .unwrap(),
+    );
+    let material = trace.term("material").unwrap();
+    assert_eq!(material.scores[0].0, material.scores[1].0);
+    let doubled = trace.term("doubled pawns").unwrap();
+    assert!(doubled.scores[PieceColor::White as usize].0 < 0);
+    assert_eq!(doubled.scores[PieceColor::Black as usize], (0, 0));
+}
+
+#[test]
+fn trace_shows_endgame_table_hit() {
+    let evaluator = Evaluator::new(EndgameTableSearcher::new());
+    // black is stalemated
+    let board_state = BoardState::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+    let trace = evaluator.trace(&board_state);
+    assert_eq!(trace.endgame_table_score, Some(0));
+    assert_eq!(trace.score, 0);
+    assert!(trace.to_string().contains("endgame tables: 0"));
+}
+
+#[test]
+fn taper_stays_below_checkmate_scores() {
+    let evaluator = Evaluator::new(EndgameTableSearcher::new());
+    let board_state = BoardState::default();
+    for score in [100_000, -100_000].iter() {
+        let tapered = taper(&board_state, (*score, *score));
+        assert!(!evaluator.is_checkmate_score(tapered));
+        assert_eq!(tapered.signum() as i32, score.signum());
+    }
+}
+
+#[test]
+fn mate_distance_from_score() {
+    let evaluator = Evaluator::new(EndgameTableSearcher::new());
+    for plies in [1, 2, 7, u8::MAX].iter() {
+        for color in [PieceColor::White, PieceColor::Black].iter() {
+            let score = evaluator.score_for_checkmate(*color, *plies);
+            assert_eq!(mate_distance(score), Some((*color, *plies)));
+        }
+    }
+    assert_eq!(mate_distance(MAX_EVALUATION as Score), None);
+    assert_eq!(mate_distance(-MAX_EVALUATION as Score), None);
+    assert_eq!(mate_distance(0), None);
+}
@@ -1,176 +1,828 @@
+use super::move_ordering::{capture_score, is_quiet, MoveOrdering};
+use super::pruning::{
+    futility_margin, late_move_reduction, null_move_allowed, null_move_reduction, PruningOptions,
+};
+use super::search_limits::SearchLimits;
+use super::transposition_table::{Bound, TranspositionEntry, TranspositionTable};
 use super::{Evaluator, Score};
-use crate::{find_legal_actions, Action, BoardState, GameEndState};
+use crate::{
+    find_legal_actions, side_to_move_in_check, Action, ActionType, BoardState, GameEndState,
+    PieceColor, PieceType,
+};
+use std::cmp::Reverse;
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+// how many nodes are searched between checks of the clock
+// it is a power of two so it can be used as a mask
+const TIME_CHECK_INTERVAL: u64 = 1024;
+// another iteration is not started once this fraction of the allocated time has been used
+// because it would almost certainly not finish in time
+const NEXT_ITERATION_TIME_FRACTION: f32 = 0.5;
+// captures that could not raise the score to alpha even with this much extra positional gain
+// are not searched by the quiescence search
+const DELTA_MARGIN: Score = 200;
+// the bounds of a full window, Score::MIN is left out so every score can be negated
+const INFINITY: Score = Score::MAX;
+// the half width of the first aspiration window, it is doubled each time the score falls
+// outside and the full window is used once it is wider than the max
+const ASPIRATION_WINDOW: Score = 50;
+const ASPIRATION_MAX_WINDOW: Score = 800;
+// shallow iterations are cheap and their scores change too much to be worth guessing
+const ASPIRATION_MIN_DEPTH: u8 = 4;
+
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    pub action: Action,
+    // the line the search expects to be played, starting with the action
+    pub pv: Vec<Action>,
+    // from blacks perspective like all other evaluation scores
+    pub score: Score,
+    // the depth of the deepest fully completed iteration
+    pub depth: u8,
+    pub nodes: u64,
+    pub elapsed: Duration,
+}
+
+impl SearchResult {
+    // the principal variation as space separated moves in uci notation
+    pub fn pv_to_uci(&self, board_state: &BoardState) -> String {
+        self.pv_to_text(board_state, |action, board_state| {
+            action.to_uci(board_state)
+        })
+    }
+
+    // the principal variation as space separated moves in standard algebraic notation
+    pub fn pv_to_san(&self, board_state: &BoardState) -> String {
+        self.pv_to_text(board_state, |action, board_state| {
+            action.to_san(board_state)
+        })
+    }
+
+    fn pv_to_text<F>(&self, board_state: &BoardState, to_text: F) -> String
+    where
+        F: Fn(&Action, &BoardState) -> String,
+    {
+        let mut board_state = board_state.clone();
+        let mut moves = Vec::with_capacity(self.pv.len());
+        for action in self.pv.iter() {
+            moves.push(to_text(action, &board_state));
+            action.play_move(&mut board_state);
+        }
+        moves.join(" ")
+    }
+}
+
+// what the search reports while it is running
+pub enum SearchProgress<'r> {
+    // how much of the whole search has been done, from 0 to 1
+    Fraction(f32),
+    // an iteration has been completed, the search can carry on with deeper ones
+    Iteration(&'r SearchResult),
+}
 
 pub struct Minimax<'a> {
     max_depth: u8,
+    limits: SearchLimits,
     evaluator: &'a Evaluator,
+    stop_signal: &'a AtomicBool,
+    transposition_table: &'a mut TranspositionTable,
+    start_time: Instant,
+    allocated_time: Option<Duration>,
+    nodes: u64,
+    aborted: bool,
+    // the principal variation of the last completed iteration, which is searched first
+    // in the next one
+    previous_pv: Vec<Action>,
+    // whether every move to reach the current node was on the previous principal variation
+    following_pv: bool,
+    move_ordering: MoveOrdering,
+    pruning: PruningOptions,
+    // set just before searching a null move so the node after it doesnt try another
+    after_null_move: bool,
 }
 
 impl<'a> Minimax<'a> {
-    pub fn new(depth: u8, evaluator: &Evaluator) -> Minimax {
-        assert!(depth != 0, "depth for minimax cant be 0");
+    pub fn new(
+        limits: SearchLimits,
+        evaluator: &'a Evaluator,
+        stop_signal: &'a AtomicBool,
+        transposition_table: &'a mut TranspositionTable,
+    ) -> Minimax<'a> {
+        assert!(limits.max_depth != 0, "depth for minimax cant be 0");
         Minimax {
-            max_depth: depth,
+            max_depth: limits.max_depth,
+            limits,
             evaluator,
+            stop_signal,
+            transposition_table,
+            start_time: Instant::now(),
+            allocated_time: limits.allocated_time(),
+            nodes: 0,
+            aborted: false,
+            previous_pv: Vec::new(),
+            following_pv: false,
+            move_ordering: MoveOrdering::new(),
+            pruning: PruningOptions::default(),
+            after_null_move: false,
         }
     }
+
+    pub fn set_pruning_options(&mut self, pruning: PruningOptions) {
+        self.pruning = pruning;
+    }
+
+    // searches one ply deeper each iteration until a limit is reached
+    // so there is always a complete shallower search to fall back on
     pub fn find_maximising_move<F>(
-        &self,
+        &mut self,
         board_state: &BoardState,
         update_progress: &mut F,
-    ) -> Result<Action, &'static str>
+    ) -> Result<SearchResult, &'static str>
     where
-        F: FnMut(f32),
+        F: FnMut(SearchProgress),
     {
-        let beta = Score::MAX;
-        let mut alpha = Score::MIN;
+        self.start_time = Instant::now();
+        self.nodes = 0;
+        self.aborted = false;
+        self.previous_pv.clear();
+        self.move_ordering.clear();
+        let legal_actions = find_legal_actions(board_state).0;
+        if legal_actions.is_empty() {
+            return Err("cant minimax an illegal board state");
+        }
+        let mut result: Option<SearchResult> = None;
+        let mut previous_score = None;
+        for depth in 1..=self.limits.max_depth {
+            self.max_depth = depth;
+            let (pv, score) = self.search_aspiration_windows(
+                board_state,
+                &legal_actions,
+                previous_score,
+                update_progress,
+            );
+            // an unfinished iteration is only used when there is nothing else to use
+            if self.aborted && result.is_some() {
+                break;
+            }
+            self.previous_pv = pv.clone();
+            previous_score = Some(score);
+            let iteration_result = SearchResult {
+                action: pv[0],
+                pv,
+                score: side_relative(score, board_state.color_turn),
+                depth,
+                nodes: self.nodes,
+                elapsed: self.start_time.elapsed(),
+            };
+            if !self.aborted {
+                update_progress(SearchProgress::Iteration(&iteration_result));
+            }
+            result = Some(iteration_result);
+            if self.aborted
+                || legal_actions.len() == 1 && self.allocated_time.is_some()
+                || self.evaluator.is_checkmate_score(score)
+                || self.time_used() > NEXT_ITERATION_TIME_FRACTION
+            {
+                break;
+            }
+        }
+        match result {
+            Some(mut result) => {
+                result.nodes = self.nodes;
+                result.elapsed = self.start_time.elapsed();
+                Ok(result)
+            }
+            None => Err("cant minimax an illegal board state"),
+        }
+    }
+
+    // the score rarely changes much between iterations so the search starts with a narrow
+    // window around the previous score, which cuts off more of the tree, and widens the
+    // side the score fell outside of until it lands inside
+    fn search_aspiration_windows<F>(
+        &mut self,
+        board_state: &BoardState,
+        legal_actions: &[Action],
+        previous_score: Option<Score>,
+        update_progress: &mut F,
+    ) -> (Vec<Action>, Score)
+    where
+        F: FnMut(SearchProgress),
+    {
+        let mut delta = ASPIRATION_WINDOW;
+        let (mut alpha, mut beta) = match previous_score {
+            Some(score)
+                if self.max_depth >= ASPIRATION_MIN_DEPTH
+                    && !self.evaluator.is_checkmate_score(score) =>
+            {
+                (
+                    score.saturating_sub(delta).max(-INFINITY),
+                    score.saturating_add(delta),
+                )
+            }
+            _ => (-INFINITY, INFINITY),
+        };
+        loop {
+            let (pv, score) = self.search_root(
+                board_state,
+                legal_actions.to_vec(),
+                alpha,
+                beta,
+                update_progress,
+            );
+            let failed_low = score <= alpha && alpha > -INFINITY;
+            let failed_high = score >= beta && beta < INFINITY;
+            if self.aborted || !failed_low && !failed_high {
+                return (pv, score);
+            }
+            delta = delta.saturating_mul(2);
+            if delta > ASPIRATION_MAX_WINDOW {
+                alpha = -INFINITY;
+                beta = INFINITY;
+            } else if failed_low {
+                alpha = score.saturating_sub(delta).max(-INFINITY);
+            } else {
+                beta = score.saturating_add(delta);
+            }
+        }
+    }
+
+    // scores are from the perspective of the side to move, like the rest of the search
+    fn search_root<F>(
+        &mut self,
+        board_state: &BoardState,
+        mut legal_actions: Vec<Action>,
+        alpha: Score,
+        beta: Score,
+        update_progress: &mut F,
+    ) -> (Vec<Action>, Score)
+    where
+        F: FnMut(SearchProgress),
+    {
+        let original_alpha = alpha;
+        let mut alpha = alpha;
         let mut best_move = None;
-        let legal_actions = find_legal_actions(&board_state, false).0;
-        let mut action_number = 0;
+        let mut pv = Vec::new();
+        let mut child_pv = Vec::new();
+        // the best move from the previous iteration is searched first
+        let tt_action = self.probe_best_action(board_state);
+        self.move_ordering.order(board_state, &mut legal_actions, 0);
+        order_first(&mut legal_actions, tt_action);
+        self.following_pv = true;
+        self.order_pv_action_first(&mut legal_actions, 0);
         let action_count = legal_actions.len();
-        for action in legal_actions {
+        for (action_number, action) in legal_actions.into_iter().enumerate() {
             let mut new_board_state = board_state.clone();
             action.play_move(&mut new_board_state);
-            let score = self.min(&new_board_state, self.max_depth - 1, alpha, beta);
+            let score = self.search_child(
+                &new_board_state,
+                self.max_depth - 1,
+                1,
+                alpha,
+                beta,
+                action_number == 0,
+                0,
+                &mut child_pv,
+            );
+            self.following_pv = false;
+            // the score of a search that was stopped part way through cant be trusted
+            // so the best move from the fully searched actions is used instead
+            if self.aborted && best_move.is_some() {
+                break;
+            }
             if best_move.is_none() || score > alpha {
-                alpha = score;
+                alpha = alpha.max(score);
                 best_move = Some(action);
+                set_line(&mut pv, action, &child_pv);
+            }
+            if alpha >= beta {
+                break;
             }
-            action_number += 1;
-            update_progress(action_number as f32 / action_count as f32);
+            update_progress(SearchProgress::Fraction(
+                self.progress((action_number + 1) as f32 / action_count as f32),
+            ));
         }
-        match best_move {
-            Some(best_move) => Ok(best_move),
-            None => Err("cant minimax an illegal board state"),
+        let bound = if alpha >= beta {
+            Bound::Lower
+        } else if alpha > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.store(board_state, self.max_depth, 0, bound, alpha, best_move);
+        self.extend_pv(board_state, &mut pv);
+        (pv, alpha)
+    }
+
+    // moves the action from the previous principal variation to the front while the
+    // search is still following it, ply is the number of moves from the root
+    fn order_pv_action_first(&mut self, actions: &mut Vec<Action>, ply: usize) {
+        if !self.following_pv {
+            return;
+        }
+        match self.previous_pv.get(ply) {
+            Some(pv_action) if actions.contains(pv_action) => {
+                order_first(actions, Some(*pv_action))
+            }
+            _ => self.following_pv = false,
         }
     }
 
-    fn min(&self, board_state: &BoardState, depth: u8, alpha: Score, beta: Score) -> Score {
-        if depth == 0 {
-            return self.evaluator.full_evaluate(&board_state);
+    // the line stops short when a node's score came from the transposition table, the
+    // exact entries are followed to fill in the rest of it
+    fn extend_pv(&self, board_state: &BoardState, pv: &mut Vec<Action>) {
+        let mut board_state = board_state.clone();
+        for action in pv.iter() {
+            action.play_move(&mut board_state);
         }
-        let mut beta = beta;
-        // only safe moves are considered on the final depth
-        let (legal_actions, game_end_option) = if depth == 1 {
-            find_legal_actions(&board_state, true)
-        } else {
-            find_legal_actions(&board_state, false)
-        };
-        if let Some(game_end) = game_end_option {
-            return self.evaluate_game_end_state(game_end, depth);
+        while pv.len() < self.max_depth as usize {
+            let action = match self.transposition_table.probe(board_state.zobrist_key()) {
+                Some(TranspositionEntry {
+                    bound: Bound::Exact,
+                    best_action: Some(action),
+                    ..
+                }) => action,
+                _ => break,
+            };
+            // a different position with the same key could have stored the entry
+            if !find_legal_actions(&board_state).0.contains(&action) {
+                break;
+            }
+            action.play_move(&mut board_state);
+            pv.push(action);
         }
+    }
 
-        let mut child_nodes: Vec<MinimaxNode> = Vec::with_capacity(legal_actions.len());
-        for action in legal_actions {
-            let mut new_board_state = board_state.clone();
-            action.play_move(&mut new_board_state);
-            child_nodes.push(MinimaxNode::new(new_board_state, &self.evaluator));
+    // progress through the whole search given the progress through the current iteration
+    fn progress(&self, iteration_progress: f32) -> f32 {
+        match self.allocated_time {
+            Some(_) => self.time_used().min(1.0),
+            None => {
+                ((self.max_depth - 1) as f32 + iteration_progress) / self.limits.max_depth as f32
+            }
         }
+    }
 
-        child_nodes.sort();
+    // the fraction of the allocated time that has been used
+    fn time_used(&self) -> f32 {
+        match self.allocated_time {
+            Some(allocated_time) => {
+                self.start_time.elapsed().as_secs_f32() / allocated_time.as_secs_f32().max(0.001)
+            }
+            None => 0.0,
+        }
+    }
 
-        // node importance controls how much the computer will think about the node
-        // the lower the value, the more important the position and the greater the depth
-        let mut node_importance = 0;
-        for node in child_nodes {
-            let depth_loss = self.depth_loss(node_importance, depth);
-            let score = self.max(&node.board_state, depth - depth_loss, alpha, beta);
-            if score <= alpha {
-                return alpha;
+    // checks every limit on the search, once one has been reached the search unwinds
+    // returning static evaluations
+    fn should_abort(&mut self) -> bool {
+        if self.aborted {
+            return true;
+        }
+        self.nodes += 1;
+        if self.stop_signal.load(Ordering::Relaxed) {
+            self.aborted = true;
+        } else if let Some(max_nodes) = self.limits.max_nodes {
+            if self.nodes >= max_nodes {
+                self.aborted = true;
             }
-            if score < beta {
-                beta = score;
+        }
+        if self.nodes & (TIME_CHECK_INTERVAL - 1) == 0 {
+            if let Some(allocated_time) = self.allocated_time {
+                if self.start_time.elapsed() >= allocated_time {
+                    self.aborted = true;
+                }
             }
-            node_importance += 1;
         }
-        return beta;
+        self.aborted
     }
 
-    fn max(&self, board_state: &BoardState, depth: u8, alpha: Score, beta: Score) -> Score {
-        if depth == 0 {
-            return self.evaluator.full_evaluate(&board_state);
+    // returns a score if the transposition table already knows enough about this position
+    // otherwise returns the best action found by a previous search of it
+    fn probe_transposition_table(
+        &self,
+        board_state: &BoardState,
+        depth: u8,
+        ply: usize,
+        alpha: Score,
+        beta: Score,
+    ) -> Result<Score, Option<Action>> {
+        match self.transposition_table.probe(board_state.zobrist_key()) {
+            Some(entry) => {
+                if entry.depth >= depth {
+                    let score = self.score_from_table(entry.score, ply);
+                    match entry.bound {
+                        Bound::Exact => return Ok(score),
+                        Bound::Lower if score >= beta => return Ok(beta),
+                        Bound::Upper if score <= alpha => return Ok(alpha),
+                        _ => (),
+                    }
+                }
+                Err(entry.best_action)
+            }
+            None => Err(None),
         }
-        let mut alpha = alpha;
-        // only safe moves are considered on the final depth
-        let (legal_actions, game_end_option) = if depth == 1 {
-            find_legal_actions(&board_state, true)
+    }
+
+    fn probe_best_action(&self, board_state: &BoardState) -> Option<Action> {
+        match self.transposition_table.probe(board_state.zobrist_key()) {
+            Some(entry) => entry.best_action,
+            None => None,
+        }
+    }
+
+    fn store(
+        &mut self,
+        board_state: &BoardState,
+        depth: u8,
+        ply: usize,
+        bound: Bound,
+        score: Score,
+        best_action: Option<Action>,
+    ) {
+        // a search that was stopped part way through will have returned incomplete scores
+        if !self.aborted {
+            self.transposition_table.store(
+                board_state.zobrist_key(),
+                depth,
+                bound,
+                self.score_to_table(score, ply),
+                best_action,
+            );
+        }
+    }
+
+    // checkmate scores count the moves from the root but the same position can be found
+    // at a different ply in a later search, so they are stored counting from the node
+    fn score_to_table(&self, score: Score, ply: usize) -> Score {
+        let ply = ply.min(u8::MAX as usize) as Score;
+        if !self.evaluator.is_checkmate_score(score) {
+            score
+        } else if score > 0 {
+            score.saturating_add(ply)
+        } else {
+            score.saturating_sub(ply).max(-INFINITY)
+        }
+    }
+
+    fn score_from_table(&self, score: Score, ply: usize) -> Score {
+        let ply = ply.min(u8::MAX as usize) as Score;
+        if !self.evaluator.is_checkmate_score(score) {
+            score
+        } else if score > 0 {
+            score - ply
         } else {
-            find_legal_actions(&board_state, false)
+            score + ply
+        }
+    }
+
+    // scores are from the perspective of the side to move, ply is the number of moves from
+    // the root, pv is filled with the best line from this node when its score is exact
+    fn negamax(
+        &mut self,
+        board_state: &BoardState,
+        depth: u8,
+        ply: usize,
+        alpha: Score,
+        beta: Score,
+        pv: &mut Vec<Action>,
+    ) -> Score {
+        pv.clear();
+        let after_null_move = mem::replace(&mut self.after_null_move, false);
+        // repeating a position could lead to a draw by repetition so it is treated as one
+        if board_state.repetition_count() > 0 {
+            return 0;
+        }
+        if depth == 0 {
+            return self.quiescence(board_state, ply, alpha, beta);
+        }
+        if self.should_abort() {
+            return self.evaluate(board_state);
+        }
+        let tt_action = match self.probe_transposition_table(board_state, depth, ply, alpha, beta) {
+            Ok(score) => return score,
+            Err(tt_action) => tt_action,
         };
+        let mut alpha = alpha;
+        let (mut legal_actions, game_end_option) = find_legal_actions(board_state);
         if let Some(game_end) = game_end_option {
-            return self.evaluate_game_end_state(game_end, depth);
+            return self.evaluate_game_end_state(game_end, board_state.color_turn, ply);
         }
-        let mut child_nodes: Vec<MinimaxNode> = Vec::with_capacity(legal_actions.len());
-        for action in legal_actions {
-            let mut new_board_state = board_state.clone();
-            action.play_move(&mut new_board_state);
-            child_nodes.push(MinimaxNode::new(new_board_state, &self.evaluator));
+        // nodes searched with a null window only have to be shown to be better or worse
+        // than alpha, only the others can be on the principal variation
+        let pv_node = alpha + 1 < beta;
+        let in_check = side_to_move_in_check(board_state);
+        let static_score = self.static_score(board_state, in_check);
+        let mut child_pv = Vec::new();
+
+        // if the side to move could pass and still be above beta then a real move will
+        // almost always do at least as well
+        if let Some(static_score) = static_score {
+            if self.pruning.null_move_pruning
+                && !pv_node
+                && !after_null_move
+                && !self.following_pv
+                && !self.evaluator.is_checkmate_score(beta)
+                && static_score >= beta
+                && null_move_allowed(board_state, depth)
+            {
+                let mut null_board_state = board_state.clone();
+                null_board_state.play_null_move();
+                self.after_null_move = true;
+                let score = -self.negamax(
+                    &null_board_state,
+                    depth.saturating_sub(1 + null_move_reduction(depth)),
+                    ply + 1,
+                    -beta,
+                    -beta + 1,
+                    &mut child_pv,
+                );
+                self.after_null_move = false;
+                if score >= beta {
+                    return beta;
+                }
+            }
         }
-        child_nodes.sort();
-        child_nodes.reverse();
+        let futile = self.is_futile(static_score, depth, alpha);
 
-        let mut node_importance = 0;
-        for node in child_nodes {
-            let depth_loss = self.depth_loss(node_importance, depth);
-            let score = self.min(&node.board_state, depth - depth_loss, alpha, beta);
+        self.order_actions(board_state, &mut legal_actions, ply, tt_action);
+        let mut best_action = None;
+        for (action_number, action) in legal_actions.into_iter().enumerate() {
+            let quiet = is_quiet(board_state, action);
+            let mut new_board_state = board_state.clone();
+            action.play_move(&mut new_board_state);
+            let gives_check = side_to_move_in_check(&new_board_state);
+            if futile && quiet && !gives_check {
+                continue;
+            }
+            let reduction = self.reduction(
+                action,
+                ply,
+                depth,
+                action_number,
+                quiet,
+                in_check || gives_check,
+            );
+            let score = self.search_child(
+                &new_board_state,
+                depth - 1,
+                ply + 1,
+                alpha,
+                beta,
+                action_number == 0,
+                reduction,
+                &mut child_pv,
+            );
+            self.following_pv = false;
             if score >= beta {
+                self.move_ordering
+                    .record_cutoff(board_state, action, depth, ply);
+                self.store(board_state, depth, ply, Bound::Lower, beta, Some(action));
                 return beta;
             }
             if score > alpha {
                 alpha = score;
+                best_action = Some(action);
+                set_line(pv, action, &child_pv);
             }
-            node_importance += 1;
         }
-        return alpha;
+        // if no move improved on alpha the real score could be even lower
+        let bound = if best_action.is_some() {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.store(board_state, depth, ply, bound, alpha, best_action);
+        alpha
     }
-    fn evaluate_game_end_state(&self, game_end_state: GameEndState, depth: u8) -> Score {
-        match game_end_state {
-            GameEndState::Draw => 0,
-            GameEndState::Win(color) => {
-                let moves_into_future = self.max_depth - depth;
-                self.evaluator.score_for_checkmate(color, moves_into_future)
-            }
+
+    // principal variation search, the first action is searched with the full window and
+    // the rest with a null window that only checks they are no better than it, which is
+    // cheaper, an action that turns out better is searched again with the full window,
+    // reduced actions are searched again to the full depth first
+    #[allow(clippy::too_many_arguments)]
+    fn search_child(
+        &mut self,
+        board_state: &BoardState,
+        depth: u8,
+        ply: usize,
+        alpha: Score,
+        beta: Score,
+        first_action: bool,
+        reduction: u8,
+        pv: &mut Vec<Action>,
+    ) -> Score {
+        if first_action {
+            return -self.negamax(board_state, depth, ply, -beta, -alpha, pv);
+        }
+        let null_window = (-alpha - 1, -alpha);
+        let mut score = -self.negamax(
+            board_state,
+            depth - reduction,
+            ply,
+            null_window.0,
+            null_window.1,
+            pv,
+        );
+        if score > alpha && reduction > 0 {
+            score = -self.negamax(board_state, depth, ply, null_window.0, null_window.1, pv);
         }
+        if score > alpha && score < beta {
+            score = -self.negamax(board_state, depth, ply, -beta, -alpha, pv);
+        }
+        score
     }
-    fn depth_loss(&self, i: u16, depth: u8) -> u8 {
-        if i < 3 || depth < 2 {
-            1
-        } else if i < 5 || depth < 3 {
-            2
+
+    // the static evaluation used by null move and futility pruning, neither is used when
+    // in check because the evaluation of a position in check cant be trusted
+    fn static_score(&self, board_state: &BoardState, in_check: bool) -> Option<Score> {
+        let pruning = self.pruning.null_move_pruning || self.pruning.futility_pruning;
+        if pruning && !in_check {
+            Some(self.evaluate(board_state))
         } else {
-            3
+            None
         }
     }
-}
 
-#[derive(Eq)]
-struct MinimaxNode {
-    board_state: BoardState,
-    estimated_score: Score,
-}
+    // whether the node is so far below alpha that no quiet action close to the leaves
+    // could raise it
+    fn is_futile(&self, static_score: Option<Score>, depth: u8, alpha: Score) -> bool {
+        if !self.pruning.futility_pruning || self.evaluator.is_checkmate_score(alpha) {
+            return false;
+        }
+        match (static_score, futility_margin(depth)) {
+            (Some(static_score), Some(margin)) => static_score.saturating_add(margin) <= alpha,
+            _ => false,
+        }
+    }
 
-impl MinimaxNode {
-    fn new(board_state: BoardState, evaluator: &Evaluator) -> MinimaxNode {
-        let estimated_score = evaluator.quick_evaluate(&board_state);
-        MinimaxNode {
-            board_state,
-            estimated_score,
+    // late quiet actions are searched less deeply because good actions are ordered first,
+    // checks and killers are never reduced
+    fn reduction(
+        &self,
+        action: Action,
+        ply: usize,
+        depth: u8,
+        action_number: usize,
+        quiet: bool,
+        check: bool,
+    ) -> u8 {
+        if !self.pruning.late_move_reductions
+            || !quiet
+            || check
+            || self.move_ordering.is_killer(action, ply)
+        {
+            return 0;
         }
+        late_move_reduction(depth, action_number)
+    }
+
+    // the actions are ordered before any of them are played so no board is cloned for an
+    // action that is never searched because of a cutoff, the best action from the
+    // transposition table goes first and the previous principal variation before that
+    fn order_actions(
+        &mut self,
+        board_state: &BoardState,
+        actions: &mut Vec<Action>,
+        ply: usize,
+        tt_action: Option<Action>,
+    ) {
+        self.move_ordering.order(board_state, actions, ply);
+        order_first(actions, tt_action);
+        self.order_pv_action_first(actions, ply);
+    }
+
+    // searches captures and promotions until the position is quiet so that the static
+    // evaluation is never made in the middle of an exchange
+    fn quiescence(
+        &mut self,
+        board_state: &BoardState,
+        ply: usize,
+        alpha: Score,
+        beta: Score,
+    ) -> Score {
+        // the side to move can usually do at least as well as the static evaluation
+        // by making a quiet move so it is used as a bound
+        let stand_pat = self.evaluate(board_state);
+        if self.should_abort() {
+            return stand_pat;
+        }
+        if stand_pat >= beta {
+            return beta;
+        }
+        let (legal_actions, game_end_option) = find_legal_actions(board_state);
+        if let Some(game_end) = game_end_option {
+            return self.evaluate_game_end_state(game_end, board_state.color_turn, ply);
+        }
+        let mut alpha = alpha.max(stand_pat);
+        for (action, gain) in self.noisy_actions(board_state, legal_actions) {
+            // the actions are ordered by gain so none of the rest can raise alpha either
+            if stand_pat.saturating_add(gain.saturating_add(DELTA_MARGIN)) <= alpha {
+                break;
+            }
+            let mut new_board_state = board_state.clone();
+            action.play_move(&mut new_board_state);
+            let score = -self.quiescence(&new_board_state, ply + 1, -beta, -alpha);
+            if score >= beta {
+                return beta;
+            }
+            if score > alpha {
+                alpha = score;
+            }
+        }
+        alpha
+    }
+
+    // the captures and queen promotions from the legal actions with the material they win,
+    // most valuable first and then by the least valuable piece making the capture
+    fn noisy_actions(
+        &self,
+        board_state: &BoardState,
+        legal_actions: Vec<Action>,
+    ) -> Vec<(Action, Score)> {
+        let mut noisy_actions: Vec<(Action, Score)> = legal_actions
+            .into_iter()
+            .filter_map(|action| {
+                let capture_gain = match action.captured_piece(board_state) {
+                    Some(piece) => self.evaluator.piece_value(piece.piece_type),
+                    None => 0,
+                };
+                let promotion_gain = match action.get_action_type() {
+                    ActionType::Promotion {
+                        from: _,
+                        to: _,
+                        piece_type: PieceType::Queen,
+                    } => {
+                        self.evaluator.piece_value(PieceType::Queen)
+                            - self.evaluator.piece_value(PieceType::Pawn)
+                    }
+                    // under promotions are never better than a queen promotion here
+                    ActionType::Promotion { .. } => return None,
+                    _ => 0,
+                };
+                if capture_gain + promotion_gain > 0 {
+                    Some((action, capture_gain + promotion_gain))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        noisy_actions.sort_by_cached_key(|(action, gain)| {
+            (Reverse(*gain), Reverse(capture_score(board_state, *action)))
+        });
+        noisy_actions
     }
-}
 
-impl PartialEq for MinimaxNode {
-    fn eq(&self, other: &Self) -> bool {
-        self.estimated_score == other.estimated_score
+    // the evaluation from the perspective of the side to move
+    fn evaluate(&self, board_state: &BoardState) -> Score {
+        side_relative(
+            self.evaluator.full_evaluate(board_state),
+            board_state.color_turn,
+        )
+    }
+
+    // ply is the number of moves from the root, quicker checkmates score higher
+    fn evaluate_game_end_state(
+        &self,
+        game_end_state: GameEndState,
+        color_turn: PieceColor,
+        ply: usize,
+    ) -> Score {
+        match game_end_state {
+            GameEndState::Draw => 0,
+            GameEndState::Win(color) => {
+                let moves_into_future = ply.min(u8::MAX as usize) as u8;
+                let score = self.evaluator.score_for_checkmate(color, moves_into_future);
+                side_relative(score, color_turn)
+            }
+        }
     }
 }
-impl PartialOrd for MinimaxNode {
-    fn partial_cmp(&self, other: &MinimaxNode) -> Option<std::cmp::Ordering> {
-        Some(self.estimated_score.cmp(&other.estimated_score))
+
+// converts a score from blacks perspective to the perspective of the given color, or back
+// again, the scores stay above Score::MIN so they can always be negated
+fn side_relative(score: Score, color: PieceColor) -> Score {
+    match color {
+        PieceColor::Black => score.max(-INFINITY),
+        PieceColor::White => score.saturating_neg(),
     }
 }
-impl Ord for MinimaxNode {
-    fn cmp(&self, other: &MinimaxNode) -> std::cmp::Ordering {
-        self.estimated_score.cmp(&other.estimated_score)
+
+// the line through a node is its best action followed by the best line after it
+fn set_line(line: &mut Vec<Action>, action: Action, child_line: &[Action]) {
+    line.clear();
+    line.push(action);
+    line.extend_from_slice(child_line);
+}
+
+// moves the action to the front keeping the order of the others
+fn order_first(actions: &mut Vec<Action>, action: Option<Action>) {
+    if let Some(action) = action {
+        if let Some(index) = actions.iter().position(|other| *other == action) {
+            actions.remove(index);
+            actions.insert(0, action);
+        }
     }
 }
@@ -0,0 +1,154 @@
+#[cfg(test)]
+mod move_ordering_tests;
+
+use crate::{Action, ActionType, BoardPosition, BoardState, PieceColor, PieceType};
+use std::cmp::Reverse;
+
+// captures are searched before the killers and the killers before the other quiet moves
+const CAPTURE_SCORE: i32 = 3_000_000;
+const KILLER_SCORE: i32 = 2_000_000;
+// the history is halved once a score reaches this so it always stays below the killers
+const MAX_HISTORY: i32 = 1_000_000;
+const KILLERS_PER_PLY: usize = 2;
+
+// orders actions before any of them are played so the ones most likely to cause a cutoff
+// are searched first, it learns from the cutoffs of the current search
+pub struct MoveOrdering {
+    // quiet actions that caused a cutoff at each ply, most recent first
+    killers: Vec<[Option<Action>; KILLERS_PER_PLY]>,
+    // how often quiet actions caused cutoffs weighted by depth, indexed by color,
+    // from square and to square
+    history: Vec<i32>,
+}
+
+impl MoveOrdering {
+    pub fn new() -> MoveOrdering {
+        MoveOrdering {
+            killers: Vec::new(),
+            history: vec![0; 2 * 64 * 64],
+        }
+    }
+    pub fn clear(&mut self) {
+        self.killers.clear();
+        for score in self.history.iter_mut() {
+            *score = 0;
+        }
+    }
+    // sorts the actions best first, ply is the number of moves from the root
+    pub fn order(&self, board_state: &BoardState, actions: &mut [Action], ply: usize) {
+        actions.sort_by_cached_key(|action| Reverse(self.score(board_state, *action, ply)));
+    }
+    pub fn score(&self, board_state: &BoardState, action: Action, ply: usize) -> i32 {
+        if !is_quiet(board_state, action) {
+            return CAPTURE_SCORE + capture_score(board_state, action);
+        }
+        if let Some(killers) = self.killers.get(ply) {
+            if let Some(index) = killers.iter().position(|killer| *killer == Some(action)) {
+                return KILLER_SCORE - index as i32;
+            }
+        }
+        self.history[history_index(board_state, action)]
+    }
+    pub fn is_killer(&self, action: Action, ply: usize) -> bool {
+        match self.killers.get(ply) {
+            Some(killers) => killers.contains(&Some(action)),
+            None => false,
+        }
+    }
+    // remembers a quiet action that was good enough to cause a cutoff, captures are
+    // already searched early so they are not recorded
+    pub fn record_cutoff(
+        &mut self,
+        board_state: &BoardState,
+        action: Action,
+        depth: u8,
+        ply: usize,
+    ) {
+        if !is_quiet(board_state, action) {
+            return;
+        }
+        if self.killers.len() <= ply {
+            self.killers.resize(ply + 1, [None; KILLERS_PER_PLY]);
+        }
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(action) {
+            killers[1] = killers[0];
+            killers[0] = Some(action);
+        }
+        // deeper cutoffs saved more work so they count for more
+        let index = history_index(board_state, action);
+        self.history[index] += depth as i32 * depth as i32;
+        if self.history[index] >= MAX_HISTORY {
+            for score in self.history.iter_mut() {
+                *score /= 2;
+            }
+        }
+    }
+}
+
+// an action that neither captures nor promotes
+pub fn is_quiet(board_state: &BoardState, action: Action) -> bool {
+    match action.get_action_type() {
+        ActionType::Promotion { .. } => false,
+        _ => action.captured_piece(board_state).is_none(),
+    }
+}
+
+// most valuable victim, least valuable attacker, taking the biggest piece comes first
+// and between equal victims the cheapest piece to lose is used, promotions count as
+// winning the piece promoted to
+pub fn capture_score(board_state: &BoardState, action: Action) -> i32 {
+    let victim = action
+        .captured_piece(board_state)
+        .map_or(0, |piece| ordering_value(piece.piece_type));
+    let promotion = match action.get_action_type() {
+        ActionType::Promotion { piece_type, .. } => ordering_value(piece_type),
+        _ => 0,
+    };
+    let attacker = match action.get_action_type() {
+        ActionType::Castling { .. } => PieceType::King,
+        ActionType::EnPassant { .. } | ActionType::Promotion { .. } => PieceType::Pawn,
+        ActionType::SimpleMove { from, .. } => match board_state.get(from) {
+            Some(piece) => piece.piece_type,
+            None => PieceType::Pawn,
+        },
+    };
+    (victim + promotion) * 8 - ordering_value(attacker)
+}
+
+fn ordering_value(piece_type: PieceType) -> i32 {
+    match piece_type {
+        PieceType::Pawn => 1,
+        PieceType::Knight => 2,
+        PieceType::Bishop => 3,
+        PieceType::Rook => 4,
+        PieceType::Queen => 5,
+        PieceType::King => 6,
+    }
+}
+
+fn history_index(board_state: &BoardState, action: Action) -> usize {
+    let (from, to) = action_squares(board_state, action);
+    let square = |position: BoardPosition| position.y as usize * 8 + position.x as usize;
+    (board_state.color_turn as usize * 64 + square(from)) * 64 + square(to)
+}
+
+// the squares a piece moves between, castling is the king's move
+fn action_squares(board_state: &BoardState, action: Action) -> (BoardPosition, BoardPosition) {
+    match action.get_action_type() {
+        ActionType::SimpleMove { from, to }
+        | ActionType::EnPassant { from, to }
+        | ActionType::Promotion { from, to, .. } => (from, to),
+        ActionType::Castling { kings_side } => {
+            let rank = match board_state.color_turn {
+                PieceColor::White => 0,
+                PieceColor::Black => 7,
+            };
+            let king_to = if kings_side { 6 } else { 2 };
+            (
+                BoardPosition::new(4, rank),
+                BoardPosition::new(king_to, rank),
+            )
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use super::{capture_score, is_quiet, MoveOrdering};
+use crate::{find_legal_actions, Action, BoardState};
+
+fn ordered_uci(move_ordering: &MoveOrdering, board_state: &BoardState, ply: usize) -> Vec<String> {
+    let mut actions = find_legal_actions(board_state).0;
+    move_ordering.order(board_state, &mut actions, ply);
+    actions
+        .iter()
+        .map(|action| action.to_uci(board_state))
+        .collect()
+}
+
+fn action(board_state: &BoardState, uci: &str) -> Action {
+    Action::from_uci(board_state, uci).unwrap()
+}
+
+#[test]
+fn most_valuable_victim_least_valuable_attacker() {
+    // the queen on d5 can be taken by the pawn or the rook and the knight on b5 by the rook
+    let board_state = BoardState::from_fen("4k3/8/8/1n1q4/2P5/8/8/1R1RK3 w - - 0 1").unwrap();
+    let order = ordered_uci(&MoveOrdering::new(), &board_state, 0);
+    assert_eq!(order[..3], ["c4d5", "d1d5", "c4b5"]);
+    assert_eq!(order[3], "b1b5");
+    assert!(
+        capture_score(&board_state, action(&board_state, "c4d5"))
+            > capture_score(&board_state, action(&board_state, "d1d5"))
+    );
+    assert!(is_quiet(&board_state, action(&board_state, "e1f2")));
+    assert!(!is_quiet(&board_state, action(&board_state, "b1b5")));
+}
+
+#[test]
+fn killers_come_after_captures() {
+    let board_state = BoardState::from_fen("4k3/8/8/3q4/2P5/8/8/4K2R w K - 0 1").unwrap();
+    let mut move_ordering = MoveOrdering::new();
+    move_ordering.record_cutoff(&board_state, action(&board_state, "h1h7"), 3, 2);
+    move_ordering.record_cutoff(&board_state, action(&board_state, "e1g1"), 3, 2);
+    // captures are never killers
+    move_ordering.record_cutoff(&board_state, action(&board_state, "c4d5"), 3, 2);
+    let order = ordered_uci(&move_ordering, &board_state, 2);
+    assert_eq!(order[..3], ["c4d5", "e1g1", "h1h7"]);
+    // killers belong to the ply they were found at
+    let order = ordered_uci(&move_ordering, &board_state, 1);
+    assert_eq!(order[0], "c4d5");
+    assert!(order[1] != "e1g1");
+}
+
+#[test]
+fn history_orders_quiet_actions() {
+    let board_state = BoardState::from_fen(crate::STARTING_FEN).unwrap();
+    let mut move_ordering = MoveOrdering::new();
+    // the killer slots only hold two actions so older cutoffs are only in the history
+    for (uci, depth) in [
+        ("g1f3", 3),
+        ("e2e4", 4),
+        ("b1c3", 2),
+        ("a2a3", 1),
+        ("h2h3", 1),
+    ]
+    .iter()
+    {
+        move_ordering.record_cutoff(&board_state, action(&board_state, uci), *depth, 0);
+    }
+    let order = ordered_uci(&move_ordering, &board_state, 1);
+    assert_eq!(order[..3], ["e2e4", "g1f3", "b1c3"]);
+    move_ordering.clear();
+    let order = ordered_uci(&move_ordering, &board_state, 0);
+    assert_eq!(order, ordered_uci(&MoveOrdering::new(), &board_state, 0));
+}
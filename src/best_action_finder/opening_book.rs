@@ -0,0 +1,214 @@
+#[cfg(test)]
+mod opening_book_tests;
+
+use crate::{Action, BoardState, FenParseError, GameEndState, NotationError, PieceColor};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// how often a move was played from a position and how those games ended
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BookMove {
+    pub action: Action,
+    pub games: u32,
+    pub white_wins: u32,
+    pub draws: u32,
+    pub black_wins: u32,
+}
+
+// the engine's own opening book, built from games with the book builder and saved as
+// text with one line per move: the fen of the position, the move in uci notation and
+// then the number of games, white wins, draws and black wins
+#[derive(Default)]
+pub struct OpeningBook {
+    positions: HashMap<BoardState, Vec<BookMove>>,
+}
+
+impl OpeningBook {
+    pub fn new() -> OpeningBook {
+        OpeningBook::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn moves(&self, board_state: &BoardState) -> &[BookMove] {
+        match self.positions.get(board_state) {
+            Some(moves) => moves,
+            None => &[],
+        }
+    }
+
+    // counts the moves of a game up to max_ply plies from its start, the result is
+    // None for games that were not finished
+    pub fn add_game(
+        &mut self,
+        starting_position: &BoardState,
+        actions: &[Action],
+        result: Option<GameEndState>,
+        max_ply: usize,
+    ) {
+        let mut board_state = starting_position.clone();
+        for action in actions.iter().take(max_ply) {
+            let book_move = BookMove {
+                action: *action,
+                games: 1,
+                white_wins: (result == Some(GameEndState::Win(PieceColor::White))) as u32,
+                draws: (result == Some(GameEndState::Draw)) as u32,
+                black_wins: (result == Some(GameEndState::Win(PieceColor::Black))) as u32,
+            };
+            self.add_move(&board_state, book_move);
+            action.play_move(&mut board_state);
+        }
+    }
+
+    fn add_move(&mut self, board_state: &BoardState, book_move: BookMove) {
+        let moves = self.positions.entry(board_state.clone()).or_default();
+        match moves
+            .iter_mut()
+            .find(|other| other.action == book_move.action)
+        {
+            Some(other) => {
+                other.games += book_move.games;
+                other.white_wins += book_move.white_wins;
+                other.draws += book_move.draws;
+                other.black_wins += book_move.black_wins;
+            }
+            None => moves.push(book_move),
+        }
+    }
+
+    // drops the moves played in fewer than min_games games
+    pub fn remove_rare_moves(&mut self, min_games: u32) {
+        for moves in self.positions.values_mut() {
+            moves.retain(|book_move| book_move.games >= min_games);
+        }
+        self.positions.retain(|_, moves| !moves.is_empty());
+    }
+
+    // picks one of the book moves with a chance proportional to the number of games it
+    // was played in, random is any number
+    pub fn choose_move(&self, board_state: &BoardState, random: u64) -> Option<Action> {
+        let moves = self.moves(board_state);
+        let total_games: u64 = moves.iter().map(|book_move| book_move.games as u64).sum();
+        if total_games == 0 {
+            return None;
+        }
+        let mut remaining = random % total_games;
+        for book_move in moves {
+            if remaining < book_move.games as u64 {
+                return Some(book_move.action);
+            }
+            remaining -= book_move.games as u64;
+        }
+        None
+    }
+
+    // positions are sorted by fen and moves by how often they were played so the same
+    // book is always written the same way
+    pub fn to_text(&self) -> String {
+        let mut lines: Vec<(String, Vec<(String, BookMove)>)> = self
+            .positions
+            .iter()
+            .map(|(board_state, moves)| {
+                let mut moves: Vec<(String, BookMove)> = moves
+                    .iter()
+                    .map(|book_move| (book_move.action.to_uci(board_state), *book_move))
+                    .collect();
+                moves.sort_by(|(uci, book_move), (other_uci, other_move)| {
+                    other_move
+                        .games
+                        .cmp(&book_move.games)
+                        .then(uci.cmp(other_uci))
+                });
+                (board_state.to_fen(), moves)
+            })
+            .collect();
+        lines.sort_by(|(fen, _), (other_fen, _)| fen.cmp(other_fen));
+        let mut text = String::new();
+        for (fen, moves) in lines {
+            for (uci, book_move) in moves {
+                text.push_str(&format!(
+                    "{} {} {} {} {} {}\n",
+                    fen,
+                    uci,
+                    book_move.games,
+                    book_move.white_wins,
+                    book_move.draws,
+                    book_move.black_wins
+                ));
+            }
+        }
+        text
+    }
+
+    // empty lines and lines starting with # are skipped
+    pub fn parse(text: &str) -> Result<OpeningBook, OpeningBookError> {
+        let mut book = OpeningBook::new();
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            // the six fields of the fen, the move and the four counts
+            if fields.len() != 11 {
+                return Err(OpeningBookError::InvalidLine(line_number));
+            }
+            let board_state = BoardState::from_fen(&fields[0..6].join(" "))
+                .map_err(|err| OpeningBookError::InvalidFen(line_number, err))?;
+            let action = Action::from_uci(&board_state, fields[6])
+                .map_err(|err| OpeningBookError::InvalidMove(line_number, err))?;
+            let mut counts = [0; 4];
+            for (count, field) in counts.iter_mut().zip(fields[7..].iter()) {
+                *count = field
+                    .parse()
+                    .map_err(|_| OpeningBookError::InvalidLine(line_number))?;
+            }
+            book.add_move(
+                &board_state,
+                BookMove {
+                    action,
+                    games: counts[0],
+                    white_wins: counts[1],
+                    draws: counts[2],
+                    black_wins: counts[3],
+                },
+            );
+        }
+        Ok(book)
+    }
+
+    pub fn load(path: &Path) -> Result<OpeningBook, OpeningBookError> {
+        OpeningBook::parse(&fs::read_to_string(path).map_err(OpeningBookError::Io)?)
+    }
+}
+
+#[derive(Debug)]
+pub enum OpeningBookError {
+    Io(io::Error),
+    InvalidLine(usize),
+    InvalidFen(usize, FenParseError),
+    InvalidMove(usize, NotationError),
+}
+
+impl fmt::Display for OpeningBookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpeningBookError::Io(err) => write!(f, "{}", err),
+            OpeningBookError::InvalidLine(line) => {
+                write!(f, "line {} should have a fen, a move and four counts", line)
+            }
+            OpeningBookError::InvalidFen(line, err) => write!(f, "line {}: {}", line, err),
+            OpeningBookError::InvalidMove(line, err) => write!(f, "line {}: {}", line, err),
+        }
+    }
+}
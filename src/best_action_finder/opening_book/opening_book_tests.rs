@@ -0,0 +1,105 @@
+use super::{OpeningBook, OpeningBookError};
+use crate::{Action, BoardState, GameEndState, PieceColor};
+
+fn play(moves: &[&str]) -> (Vec<Action>, BoardState) {
+    let mut board_state = BoardState::default();
+    let mut actions = Vec::new();
+    for uci in moves {
+        let action = Action::from_uci(&board_state, uci).unwrap();
+        action.play_move(&mut board_state);
+        actions.push(action);
+    }
+    (actions, board_state)
+}
+
+fn example_book() -> OpeningBook {
+    let start = BoardState::default();
+    let mut book = OpeningBook::new();
+    let (actions, _) = play(&["g1f3", "g8f6", "b1c3", "b8c6", "e2e4"]);
+    book.add_game(&start, &actions, Some(GameEndState::Draw), 10);
+    let (actions, _) = play(&["b1c3", "b8c6", "g1f3", "g8f6", "d2d4"]);
+    book.add_game(
+        &start,
+        &actions,
+        Some(GameEndState::Win(PieceColor::White)),
+        10,
+    );
+    let (actions, _) = play(&["g1f3", "d7d5"]);
+    book.add_game(&start, &actions, None, 1);
+    book
+}
+
+#[test]
+fn moves_are_counted_per_position() {
+    let book = example_book();
+    let (_, start) = play(&[]);
+    let moves = book.moves(&start);
+    assert_eq!(moves.len(), 2);
+    assert_eq!((moves[0].games, moves[0].draws), (2, 1));
+    assert_eq!((moves[1].games, moves[1].white_wins), (1, 1));
+    // the same position reached by a different move order
+    let (_, transposed) = play(&["g1f3", "g8f6", "b1c3", "b8c6"]);
+    assert_eq!(book.moves(&transposed).len(), 2);
+    // the third game stopped after its first ply
+    let (_, after_nf3) = play(&["g1f3"]);
+    assert_eq!(book.moves(&after_nf3).len(), 1);
+    let (_, after_d4) = play(&["b1c3", "b8c6", "g1f3", "g8f6", "d2d4"]);
+    assert!(book.moves(&after_d4).is_empty());
+}
+
+#[test]
+fn remove_rare_moves() {
+    let mut book = example_book();
+    assert_eq!(book.len(), 8);
+    book.remove_rare_moves(2);
+    let (_, start) = play(&[]);
+    assert_eq!(book.len(), 1);
+    assert_eq!(book.moves(&start).len(), 1);
+}
+
+#[test]
+fn choose_move_by_games_played() {
+    let book = example_book();
+    let (nf3, _) = play(&["g1f3"]);
+    let (nc3, _) = play(&["b1c3"]);
+    let start = BoardState::default();
+    assert_eq!(book.choose_move(&start, 0), Some(nf3[0]));
+    assert_eq!(book.choose_move(&start, 1), Some(nf3[0]));
+    assert_eq!(book.choose_move(&start, 2), Some(nc3[0]));
+    assert_eq!(book.choose_move(&start, 3), Some(nf3[0]));
+    let (_, after_d4) = play(&["b1c3", "b8c6", "g1f3", "g8f6", "d2d4"]);
+    assert_eq!(book.choose_move(&after_d4, 0), None);
+}
+
+#[test]
+fn text_round_trip() {
+    let book = example_book();
+    let text = book.to_text();
+    assert!(text.starts_with("r1bqkb1r/pppppppp/2n2n2/8/8/2N2N2/PPPPPPPP/R1BQKB1R w KQkq - 4 3 "));
+    assert!(
+        text.contains("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 g1f3 2 0 1 0\n")
+    );
+    let parsed = OpeningBook::parse(&format!("# comment\n\n{}", text)).unwrap();
+    assert_eq!(parsed.to_text(), text);
+}
+
+#[test]
+fn parse_errors() {
+    let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert!(matches!(
+        OpeningBook::parse(&format!("{} e2e4 1 0 1\n", fen)),
+        Err(OpeningBookError::InvalidLine(1))
+    ));
+    assert!(matches!(
+        OpeningBook::parse(&format!("\n{} e2e4 1 0 one 0\n", fen)),
+        Err(OpeningBookError::InvalidLine(2))
+    ));
+    assert!(matches!(
+        OpeningBook::parse(&format!("{} e2e5 1 0 1 0\n", fen)),
+        Err(OpeningBookError::InvalidMove(1, _))
+    ));
+    assert!(matches!(
+        OpeningBook::parse("rnbqkbnr/pppppppp/8/8 w KQkq - 0 1 e2e4 1 0 1 0\n"),
+        Err(OpeningBookError::InvalidFen(1, _))
+    ));
+}
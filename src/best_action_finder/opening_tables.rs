@@ -1,16 +1,83 @@
-use crate::{Action, ActionType, BoardPosition, BoardState};
+use super::opening_book::OpeningBook;
+use super::polyglot_book::{PolyglotBook, PolyglotKeys};
+use crate::{Action, ActionType, BoardPosition, BoardState, PieceColor};
 use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// the number of plies from the start of the game that book moves are played for
+pub const DEFAULT_BOOK_DEPTH: u16 = 16;
+const POLYGLOT_BOOK_FILE: &str = "books/book.bin";
+const KEYS_FILE: &str = "books/polyglot_random64.txt";
+// a book made by the book builder
+const OPENING_BOOK_FILE: &str = "books/book.txt";
 
 pub struct OpeningTables {
+    // books from the resources directory, when there are any
+    polyglot_book: Option<PolyglotBook>,
+    opening_book: Option<OpeningBook>,
+    // the built in moves used when there is no book or it has nothing for the position
     entrys: HashMap<BoardState, Action>,
+    book_depth: u16,
+    random_state: u64,
 }
 
 impl OpeningTables {
     pub fn new() -> OpeningTables {
+        let resource_dir = if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
+            let mut path = PathBuf::from(manifest_dir);
+            path.push("resources");
+            path
+        } else {
+            PathBuf::from("./resources")
+        };
+        // without a book there is nothing to report, the built in moves are used quietly
+        let polyglot_path = resource_dir.join(POLYGLOT_BOOK_FILE);
+        let polyglot_book = if polyglot_path.exists() {
+            match PolyglotKeys::load(&resource_dir.join(KEYS_FILE))
+                .and_then(|keys| PolyglotBook::load(keys, &polyglot_path))
+            {
+                Ok(book) => Some(book),
+                Err(err) => {
+                    eprintln!(
+                        "could not load opening book {}: {}",
+                        polyglot_path.display(),
+                        err
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let opening_book_path = resource_dir.join(OPENING_BOOK_FILE);
+        let opening_book = if opening_book_path.exists() {
+            match OpeningBook::load(&opening_book_path) {
+                Ok(book) => Some(book),
+                Err(err) => {
+                    eprintln!(
+                        "could not load opening book {}: {}",
+                        opening_book_path.display(),
+                        err
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        OpeningTables::with_books(polyglot_book, opening_book)
+    }
+    pub fn with_books(
+        polyglot_book: Option<PolyglotBook>,
+        opening_book: Option<OpeningBook>,
+    ) -> OpeningTables {
         let mut entrys = HashMap::new();
         // Kings pawn opening e5
         entrys.insert(
-            BoardState::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+            BoardState::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1")
+                .unwrap(),
             Action::new(ActionType::SimpleMove {
                 from: BoardPosition::from_text("e7"),
                 to: BoardPosition::from_text("e5"),
@@ -18,7 +85,8 @@ impl OpeningTables {
         );
         // Kings pawn opening kings knight nc6
         entrys.insert(
-            BoardState::from_fen("rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"),
+            BoardState::from_fen("rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2")
+                .unwrap(),
             Action::new(ActionType::SimpleMove {
                 from: BoardPosition::from_text("b8"),
                 to: BoardPosition::from_text("c6"),
@@ -28,7 +96,8 @@ impl OpeningTables {
         entrys.insert(
             BoardState::from_fen(
                 "r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R b KQkq - 3 3",
-            ),
+            )
+            .unwrap(),
             Action::new(ActionType::SimpleMove {
                 from: BoardPosition::from_text("f8"),
                 to: BoardPosition::from_text("c5"),
@@ -38,7 +107,8 @@ impl OpeningTables {
         entrys.insert(
             BoardState::from_fen(
                 "r1bqkbnr/pppp1ppp/2n5/1B2p3/4P3/5N2/PPPP1PPP/RNBQK2R b KQkq - 3 3",
-            ),
+            )
+            .unwrap(),
             Action::new(ActionType::SimpleMove {
                 from: BoardPosition::from_text("a7"),
                 to: BoardPosition::from_text("a6"),
@@ -46,7 +116,8 @@ impl OpeningTables {
         );
         // Indian Game
         entrys.insert(
-            BoardState::from_fen("rnbqkbnr/pppppppp/8/8/3P4/8/PPP1PPPP/RNBQKBNR b KQkq d3 0 1"),
+            BoardState::from_fen("rnbqkbnr/pppppppp/8/8/3P4/8/PPP1PPPP/RNBQKBNR b KQkq d3 0 1")
+                .unwrap(),
             Action::new(ActionType::SimpleMove {
                 from: BoardPosition::from_text("g8"),
                 to: BoardPosition::from_text("f6"),
@@ -54,19 +125,53 @@ impl OpeningTables {
         );
         // Indian Game East Indian Defense
         entrys.insert(
-            BoardState::from_fen("rnbqkb1r/pppppppp/5n2/8/2PP4/8/PP2PPPP/RNBQKBNR b KQkq c3 0 2"),
+            BoardState::from_fen("rnbqkb1r/pppppppp/5n2/8/2PP4/8/PP2PPPP/RNBQKBNR b KQkq c3 0 2")
+                .unwrap(),
             Action::new(ActionType::SimpleMove {
                 from: BoardPosition::from_text("e7"),
                 to: BoardPosition::from_text("e6"),
             }),
         );
-        OpeningTables { entrys }
+        // book moves only need to vary from game to game so the clock is a good enough seed
+        let random_state = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_nanos() as u64)
+            .unwrap_or(0);
+        OpeningTables {
+            polyglot_book,
+            opening_book,
+            entrys,
+            book_depth: DEFAULT_BOOK_DEPTH,
+            random_state,
+        }
     }
-    pub fn try_find_move(&self, board_state: &BoardState) -> Option<Action> {
-        if self.entrys.contains_key(board_state) {
-            Some(self.entrys[board_state])
-        } else {
-            None
+    // a depth of zero turns the book off
+    pub fn set_book_depth(&mut self, book_depth: u16) {
+        self.book_depth = book_depth;
+    }
+    pub fn try_find_move(&mut self, board_state: &BoardState) -> Option<Action> {
+        let ply = (board_state.fullmove_number.max(1) as u32 - 1) * 2
+            + (board_state.color_turn == PieceColor::Black) as u32;
+        if ply >= self.book_depth as u32 {
+            return None;
         }
+        let random = self.next_random();
+        let polyglot_move = match &self.polyglot_book {
+            Some(book) => book.choose_move(board_state, random),
+            None => None,
+        };
+        let book_move = polyglot_move.or_else(|| match &self.opening_book {
+            Some(book) => book.choose_move(board_state, random),
+            None => None,
+        });
+        book_move.or_else(|| self.entrys.get(board_state).copied())
+    }
+    // splitmix64
+    fn next_random(&mut self) -> u64 {
+        self.random_state = self.random_state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.random_state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
     }
 }
@@ -0,0 +1,176 @@
+#[cfg(test)]
+mod pawn_structure_tests;
+
+use super::eval_params::EvalParams;
+use crate::actions::attacks::pawn_attacks;
+use crate::board_state::bitboard::{squares, Bitboard};
+use crate::{BoardPosition, BoardState, PieceColor, PieceType};
+
+const PAWN_HASH_TABLE_SIZE: usize = 1 << 14;
+const FILE_A: Bitboard = 0x0101_0101_0101_0101;
+
+// the weaknesses and passed pawns of each side, indexed by color
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PawnStructure {
+    pub doubled: [u8; 2],
+    pub isolated: [u8; 2],
+    pub backward: [u8; 2],
+    pub passed: [Bitboard; 2],
+}
+
+impl PawnStructure {
+    pub fn new(board_state: &BoardState) -> PawnStructure {
+        let mut structure = PawnStructure::default();
+        for color in [PieceColor::White, PieceColor::Black].iter() {
+            let color = *color;
+            let own_pawns = board_state.piece_bitboard(color, PieceType::Pawn);
+            let enemy_pawns = board_state.piece_bitboard(color.opposite_color(), PieceType::Pawn);
+            let index = color as usize;
+            for x in 0..8 {
+                let count = (own_pawns & file_mask(x)).count_ones() as u8;
+                structure.doubled[index] += count.saturating_sub(1);
+            }
+            for pos in squares(own_pawns) {
+                let ahead = ranks_ahead(color, pos.y);
+                let neighbours = adjacent_files_mask(pos.x);
+                if enemy_pawns & (file_mask(pos.x) | neighbours) & ahead == 0 {
+                    structure.passed[index] |= 1 << pos.to_index();
+                }
+                if own_pawns & neighbours == 0 {
+                    structure.isolated[index] += 1;
+                } else if own_pawns & neighbours & !ahead == 0
+                    && is_stop_square_attacked(color, pos, enemy_pawns)
+                {
+                    // every pawn that could defend it has already moved past it
+                    structure.backward[index] += 1;
+                }
+            }
+        }
+        structure
+    }
+
+    // middlegame and endgame scores from black's side, like the rest of the evaluation
+    pub fn score(&self, params: &EvalParams) -> (i32, i32) {
+        let mut middlegame = 0;
+        let mut endgame = 0;
+        for color in [PieceColor::White, PieceColor::Black].iter() {
+            let sign = match color {
+                PieceColor::White => -1,
+                PieceColor::Black => 1,
+            };
+            for (term_middlegame, term_endgame) in self.terms(*color, params).iter() {
+                middlegame += sign * term_middlegame;
+                endgame += sign * term_endgame;
+            }
+        }
+        (middlegame, endgame)
+    }
+
+    // the doubled, isolated, backward and passed pawn scores of one color, higher is
+    // better for that color so the weaknesses are negative
+    pub fn terms(&self, color: PieceColor, params: &EvalParams) -> [(i32, i32); 4] {
+        let index = color as usize;
+        let penalty = |count: u8, (middlegame, endgame): (i32, i32)| {
+            (-(count as i32) * middlegame, -(count as i32) * endgame)
+        };
+        let mut passed = (0, 0);
+        for pos in squares(self.passed[index]) {
+            let advanced = match color {
+                PieceColor::White => pos.y,
+                PieceColor::Black => 7 - pos.y,
+            } as usize;
+            passed.0 += params.passed_pawn_middlegame_bonus[advanced];
+            passed.1 += params.passed_pawn_endgame_bonus[advanced];
+        }
+        [
+            penalty(self.doubled[index], params.doubled_pawn_penalty),
+            penalty(self.isolated[index], params.isolated_pawn_penalty),
+            penalty(self.backward[index], params.backward_pawn_penalty),
+            passed,
+        ]
+    }
+}
+
+pub fn file_mask(x: u8) -> Bitboard {
+    FILE_A << x
+}
+
+pub fn adjacent_files_mask(x: u8) -> Bitboard {
+    let mut mask = 0;
+    if x > 0 {
+        mask |= file_mask(x - 1);
+    }
+    if x < 7 {
+        mask |= file_mask(x + 1);
+    }
+    mask
+}
+
+// the ranks in front of a pawn of the color on rank y
+pub fn ranks_ahead(color: PieceColor, y: u8) -> Bitboard {
+    match color {
+        PieceColor::White if y < 7 => !0 << (8 * (y + 1)),
+        PieceColor::Black if y > 0 => !0 >> (8 * (8 - y)),
+        _ => 0,
+    }
+}
+
+// whether the square the pawn would move to is guarded by an enemy pawn
+fn is_stop_square_attacked(color: PieceColor, pos: BoardPosition, enemy_pawns: Bitboard) -> bool {
+    let stop_y = match color {
+        PieceColor::White => pos.y + 1,
+        PieceColor::Black => pos.y.wrapping_sub(1),
+    };
+    stop_y < 8 && pawn_attacks(color, BoardPosition::new(pos.x, stop_y)) & enemy_pawns != 0
+}
+
+#[derive(Clone, Copy)]
+struct PawnHashEntry {
+    pawns: [Bitboard; 2],
+    score: (i32, i32),
+}
+
+// the pawn structure changes far less often than the rest of the board so its score is
+// cached, keyed on where the pawns are
+pub struct PawnHashTable {
+    entries: Vec<Option<PawnHashEntry>>,
+}
+
+impl PawnHashTable {
+    pub fn new() -> PawnHashTable {
+        PawnHashTable {
+            entries: vec![None; PAWN_HASH_TABLE_SIZE],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for entry in self.entries.iter_mut() {
+            *entry = None;
+        }
+    }
+
+    pub fn score(&mut self, board_state: &BoardState, params: &EvalParams) -> (i32, i32) {
+        let pawns = [
+            board_state.piece_bitboard(PieceColor::White, PieceType::Pawn),
+            board_state.piece_bitboard(PieceColor::Black, PieceType::Pawn),
+        ];
+        let index = (pawn_key(pawns) % PAWN_HASH_TABLE_SIZE as u64) as usize;
+        match self.entries[index] {
+            Some(entry) if entry.pawns == pawns => entry.score,
+            _ => {
+                let score = PawnStructure::new(board_state).score(params);
+                self.entries[index] = Some(PawnHashEntry { pawns, score });
+                score
+            }
+        }
+    }
+}
+
+// a hash of only the pawns, the entry keeps the pawns themselves so collisions are
+// never mistaken for a match
+fn pawn_key(pawns: [Bitboard; 2]) -> u64 {
+    let mut key = pawns[0].wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ pawns[1].rotate_left(32);
+    key = (key ^ (key >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    key = (key ^ (key >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    key ^ (key >> 31)
+}
@@ -0,0 +1,91 @@
+use super::{PawnHashTable, PawnStructure};
+use crate::best_action_finder::EvalParams;
+use crate::board_state::bitboard::{squares, Bitboard};
+use crate::{BoardState, PieceColor};
+
+const WHITE: usize = PieceColor::White as usize;
+const BLACK: usize = PieceColor::Black as usize;
+
+fn pawn_structure(fen: &str) -> PawnStructure {
+    PawnStructure::new(&BoardState::from_fen(fen).unwrap())
+}
+
+fn square_names(bitboard: Bitboard) -> Vec<String> {
+    squares(bitboard).map(|pos| pos.to_text()).collect()
+}
+
+#[test]
+fn starting_position_has_no_weaknesses() {
+    assert_eq!(
+        pawn_structure(crate::STARTING_FEN),
+        PawnStructure::default()
+    );
+}
+
+#[test]
+fn doubled_pawns() {
+    // white has two extra pawns on the c file, black one on the g file
+    let structure = pawn_structure("4k3/5pp1/6p1/8/2P5/2P5/2P5/4K3 w - - 0 1");
+    assert_eq!(structure.doubled, [2, 1]);
+}
+
+#[test]
+fn isolated_pawns() {
+    let structure = pawn_structure("4k3/p1p2ppp/8/8/8/8/P2PP2P/4K3 w - - 0 1");
+    assert_eq!(structure.isolated[WHITE], 2);
+    assert_eq!(structure.isolated[BLACK], 2);
+}
+
+#[test]
+fn backward_pawns() {
+    // the d3 pawn can't advance past the pawn on e5 and its neighbours on c4 and e4
+    // have moved past it, black's d6 pawn is supported by the one on c7
+    let structure = pawn_structure("4k3/2p5/3p4/4p3/2P1P3/3P4/8/4K3 w - - 0 1");
+    assert_eq!(structure.backward[WHITE], 1);
+    assert_eq!(structure.backward[BLACK], 0);
+    // the pawn on b2 is behind its neighbour but nothing stops it moving up
+    let structure = pawn_structure("4k3/8/8/8/8/2P5/1P6/4K3 w - - 0 1");
+    assert_eq!(structure.backward[WHITE], 0);
+}
+
+#[test]
+fn passed_pawns() {
+    // the pawn on b7 can take either of the a pawns as they go past
+    let structure = pawn_structure("4k3/1p6/8/P2p4/8/2p5/P4P2/4K3 w - - 0 1");
+    assert_eq!(square_names(structure.passed[WHITE]), vec!["f2"]);
+    assert_eq!(square_names(structure.passed[BLACK]), vec!["c3", "d5"]);
+}
+
+#[test]
+fn passed_pawns_score_more_when_advanced() {
+    let score = |fen: &str| pawn_structure(fen).score(&EvalParams::default());
+    let (middlegame, endgame) = score("4k3/8/8/8/8/8/P7/4K3 w - - 0 1");
+    let (advanced_middlegame, advanced_endgame) = score("4k3/P7/8/8/8/8/8/4K3 w - - 0 1");
+    // scores are from black's side
+    assert!(advanced_middlegame < middlegame);
+    assert!(advanced_endgame < endgame);
+    assert!(advanced_endgame < advanced_middlegame);
+    assert_eq!(
+        score("4k3/8/8/8/8/8/P7/4K3 w - - 0 1").0,
+        -score("4k3/p7/8/8/8/8/8/4K3 w - - 0 1").0
+    );
+}
+
+#[test]
+fn hash_table_matches_structure() {
+    let params = EvalParams::default();
+    let mut pawn_hash_table = PawnHashTable::new();
+    for fen in [
+        crate::STARTING_FEN,
+        "4k3/2p5/3p4/4p3/2P1P3/3P4/8/4K3 w - - 0 1",
+        "4k3/1p6/8/P2p4/8/2p5/P4P2/4K3 w - - 0 1",
+    ]
+    .iter()
+    {
+        let board_state = BoardState::from_fen(fen).unwrap();
+        let expected = PawnStructure::new(&board_state).score(&params);
+        assert_eq!(pawn_hash_table.score(&board_state, &params), expected);
+        // the second lookup comes from the cache
+        assert_eq!(pawn_hash_table.score(&board_state, &params), expected);
+    }
+}
@@ -0,0 +1,226 @@
+#[cfg(test)]
+mod piece_activity_tests;
+
+use super::eval_params::EvalParams;
+use super::pawn_structure::{adjacent_files_mask, file_mask, ranks_ahead};
+use crate::actions::attacks::{
+    bishop_attacks, king_attacks, knight_attacks, pawn_attacks, rook_attacks,
+};
+use crate::board_state::bitboard::{squares, Bitboard};
+use crate::{BoardPosition, BoardState, PieceColor, PieceType};
+
+const COLORS: [PieceColor; 2] = [PieceColor::White, PieceColor::Black];
+
+// how much each term counts as a percentage of its normal value, a term can be turned
+// off with 0 to measure what it is worth
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TermWeights {
+    pub mobility: i32,
+    pub king_safety: i32,
+    pub rook_files: i32,
+    pub bishop_pair: i32,
+    pub outposts: i32,
+}
+
+impl Default for TermWeights {
+    fn default() -> TermWeights {
+        TermWeights {
+            mobility: 100,
+            king_safety: 100,
+            rook_files: 100,
+            bishop_pair: 100,
+            outposts: 100,
+        }
+    }
+}
+
+// the middlegame and endgame score of each term for each color, indexed by color,
+// higher is better for that color
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PieceActivity {
+    pub mobility: [(i32, i32); 2],
+    pub king_safety: [(i32, i32); 2],
+    pub rook_files: [(i32, i32); 2],
+    pub bishop_pair: [(i32, i32); 2],
+    pub outposts: [(i32, i32); 2],
+}
+
+impl PieceActivity {
+    pub fn new(board_state: &BoardState, params: &EvalParams) -> PieceActivity {
+        let mut activity = PieceActivity::default();
+        let occupied = board_state.occupied_bitboard();
+        let all_pawns = board_state.piece_bitboard(PieceColor::White, PieceType::Pawn)
+            | board_state.piece_bitboard(PieceColor::Black, PieceType::Pawn);
+        for color in COLORS.iter() {
+            let color = *color;
+            let index = color as usize;
+            let own_pieces = board_state.color_bitboard(color);
+            let own_pawns = board_state.piece_bitboard(color, PieceType::Pawn);
+            let enemy_pawns = board_state.piece_bitboard(color.opposite_color(), PieceType::Pawn);
+            let pieces = |piece_type| board_state.piece_bitboard(color, piece_type);
+
+            let mut mobility = (0, 0);
+            let mut add_mobility = |attacks: Bitboard, (middlegame, endgame): (i32, i32)| {
+                let moves = (attacks & !own_pieces).count_ones() as i32;
+                mobility.0 += moves * middlegame;
+                mobility.1 += moves * endgame;
+            };
+            for pos in squares(pieces(PieceType::Knight)) {
+                add_mobility(knight_attacks(pos), params.knight_mobility);
+            }
+            for pos in squares(pieces(PieceType::Bishop)) {
+                add_mobility(bishop_attacks(pos, occupied), params.bishop_mobility);
+            }
+            for pos in squares(pieces(PieceType::Rook)) {
+                add_mobility(rook_attacks(pos, occupied), params.rook_mobility);
+            }
+            for pos in squares(pieces(PieceType::Queen)) {
+                let attacks = bishop_attacks(pos, occupied) | rook_attacks(pos, occupied);
+                add_mobility(attacks, params.queen_mobility);
+            }
+            activity.mobility[index] = mobility;
+
+            if let Some(king) = board_state.king_position(color) {
+                activity.king_safety[index] = king_safety(board_state, params, color, king);
+            }
+
+            for pos in squares(pieces(PieceType::Rook)) {
+                let file = file_mask(pos.x);
+                if all_pawns & file == 0 {
+                    add(
+                        &mut activity.rook_files[index],
+                        params.rook_on_open_file_bonus,
+                    );
+                } else if own_pawns & file == 0 {
+                    add(
+                        &mut activity.rook_files[index],
+                        params.rook_on_semi_open_file_bonus,
+                    );
+                }
+            }
+
+            if pieces(PieceType::Bishop).count_ones() >= 2 {
+                activity.bishop_pair[index] = params.bishop_pair_bonus;
+            }
+
+            let outposts = [
+                (PieceType::Knight, params.knight_outpost_bonus),
+                (PieceType::Bishop, params.bishop_outpost_bonus),
+            ];
+            for (piece_type, bonus) in outposts.iter() {
+                for pos in squares(pieces(*piece_type)) {
+                    if is_outpost(color, pos, own_pawns, enemy_pawns) {
+                        add(&mut activity.outposts[index], *bonus);
+                    }
+                }
+            }
+        }
+        activity
+    }
+
+    // the weighted total as middlegame and endgame scores from black's side
+    pub fn score(&self, weights: &TermWeights) -> (i32, i32) {
+        let mut middlegame = 0;
+        let mut endgame = 0;
+        for term in self.weighted_terms(weights).iter() {
+            let white = term[PieceColor::White as usize];
+            let black = term[PieceColor::Black as usize];
+            middlegame += black.0 - white.0;
+            endgame += black.1 - white.1;
+        }
+        (middlegame, endgame)
+    }
+
+    // the mobility, king safety, rook file, bishop pair and outpost scores for each
+    // color with the weights applied
+    pub fn weighted_terms(&self, weights: &TermWeights) -> [[(i32, i32); 2]; 5] {
+        let weigh = |term: &[(i32, i32); 2], weight: i32| {
+            let mut weighted = *term;
+            for (middlegame, endgame) in weighted.iter_mut() {
+                *middlegame = *middlegame * weight / 100;
+                *endgame = *endgame * weight / 100;
+            }
+            weighted
+        };
+        [
+            weigh(&self.mobility, weights.mobility),
+            weigh(&self.king_safety, weights.king_safety),
+            weigh(&self.rook_files, weights.rook_files),
+            weigh(&self.bishop_pair, weights.bishop_pair),
+            weigh(&self.outposts, weights.outposts),
+        ]
+    }
+}
+
+fn add(score: &mut (i32, i32), (middlegame, endgame): (i32, i32)) {
+    score.0 += middlegame;
+    score.1 += endgame;
+}
+
+// rewards pawns sheltering the king and penalises enemy pieces attacking the squares
+// around it
+fn king_safety(
+    board_state: &BoardState,
+    params: &EvalParams,
+    color: PieceColor,
+    king: BoardPosition,
+) -> (i32, i32) {
+    let mut score = (0, 0);
+    let home_rank = match color {
+        PieceColor::White => 0,
+        PieceColor::Black => 7,
+    };
+    if (king.y as i32 - home_rank).abs() <= 1 {
+        // the two ranks in front of the king on its file and the files either side
+        let ahead = ranks_ahead(color, king.y);
+        let two_ranks_ahead = match color {
+            PieceColor::White => ahead & !ranks_ahead(color, (king.y + 2).min(7)),
+            PieceColor::Black => ahead & !ranks_ahead(color, king.y.saturating_sub(2)),
+        };
+        let files = file_mask(king.x) | adjacent_files_mask(king.x);
+        let own_pawns = board_state.piece_bitboard(color, PieceType::Pawn);
+        let shield = (own_pawns & files & two_ranks_ahead).count_ones() as i32;
+        score.0 += shield * params.pawn_shield_bonus.0;
+        score.1 += shield * params.pawn_shield_bonus.1;
+    }
+
+    let zone = king_attacks(king) | 1 << king.to_index();
+    let enemy = color.opposite_color();
+    let occupied = board_state.occupied_bitboard();
+    let enemy_pieces = |piece_type| board_state.piece_bitboard(enemy, piece_type);
+    let mut attackers = 0;
+    for pos in squares(enemy_pieces(PieceType::Knight)) {
+        attackers += (knight_attacks(pos) & zone != 0) as usize;
+    }
+    for pos in squares(enemy_pieces(PieceType::Bishop)) {
+        attackers += (bishop_attacks(pos, occupied) & zone != 0) as usize;
+    }
+    for pos in squares(enemy_pieces(PieceType::Rook)) {
+        attackers += (rook_attacks(pos, occupied) & zone != 0) as usize;
+    }
+    for pos in squares(enemy_pieces(PieceType::Queen)) {
+        let attacks = bishop_attacks(pos, occupied) | rook_attacks(pos, occupied);
+        attackers += (attacks & zone != 0) as usize;
+    }
+    score.0 -=
+        params.king_attackers_penalty[attackers.min(params.king_attackers_penalty.len() - 1)];
+    score
+}
+
+// a square in the enemy half that is defended by a pawn and that no enemy pawn can
+// ever attack
+fn is_outpost(
+    color: PieceColor,
+    pos: BoardPosition,
+    own_pawns: Bitboard,
+    enemy_pawns: Bitboard,
+) -> bool {
+    let advanced = match color {
+        PieceColor::White => pos.y,
+        PieceColor::Black => 7 - pos.y,
+    };
+    let defended = pawn_attacks(color.opposite_color(), pos) & own_pawns != 0;
+    (3..=5).contains(&advanced)
+        && defended
+        && enemy_pawns & adjacent_files_mask(pos.x) & ranks_ahead(color, pos.y) == 0
+}
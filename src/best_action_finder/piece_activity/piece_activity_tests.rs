@@ -0,0 +1,81 @@
+use super::{PieceActivity, TermWeights};
+use crate::best_action_finder::EvalParams;
+use crate::{BoardState, PieceColor};
+
+const WHITE: usize = PieceColor::White as usize;
+const BLACK: usize = PieceColor::Black as usize;
+
+fn activity(fen: &str) -> PieceActivity {
+    PieceActivity::new(&BoardState::from_fen(fen).unwrap(), &EvalParams::default())
+}
+
+#[test]
+fn mobility() {
+    let centre = activity("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1");
+    let corner = activity("4k3/8/8/8/8/8/8/N3K3 w - - 0 1");
+    assert_eq!(centre.mobility[WHITE], (32, 32));
+    assert_eq!(corner.mobility[WHITE], (8, 8));
+    // squares taken by the bishop's own pieces don't count
+    let blocked = activity("4k3/8/8/8/8/8/1P1P4/2B1K3 w - - 0 1");
+    assert_eq!(blocked.mobility[WHITE], (0, 0));
+    assert_eq!(blocked.mobility[BLACK], (0, 0));
+}
+
+#[test]
+fn king_safety() {
+    let sheltered = activity("4k3/8/8/8/8/8/5PPP/6K1 w - - 0 1");
+    let exposed = activity("4k3/8/8/8/5PPP/8/8/6K1 w - - 0 1");
+    assert_eq!(sheltered.king_safety[WHITE], (36, 0));
+    assert_eq!(exposed.king_safety[WHITE], (0, 0));
+    // the queen, rook and knight all attack the squares next to the king
+    let attacked = activity("4k3/8/8/8/6q1/5n2/5PPP/6Kr w - - 0 1");
+    assert_eq!(attacked.king_safety[WHITE], (36 - 35, 0));
+}
+
+#[test]
+fn rook_files() {
+    let fen = "r3k2r/1p5p/8/8/8/8/P6P/R3K2R w - - 0 1";
+    let rooks = activity(fen);
+    // white's rooks are both on closed files, black's a rook is on a semi-open file
+    // and the h rook is on a closed file
+    assert_eq!(rooks.rook_files[WHITE], (0, 0));
+    assert_eq!(rooks.rook_files[BLACK], (10, 5));
+    let open = activity("3rk3/8/8/8/8/8/8/3RK3 w - - 0 1");
+    assert_eq!(open.rook_files[WHITE], (20, 10));
+}
+
+#[test]
+fn bishop_pair() {
+    let pair = activity("2b1kb2/8/8/8/8/8/8/2B1K3 w - - 0 1");
+    assert_eq!(pair.bishop_pair[WHITE], (0, 0));
+    assert_eq!(pair.bishop_pair[BLACK], (30, 50));
+}
+
+#[test]
+fn outposts() {
+    let outpost = activity("4k3/8/8/3N4/4P3/8/8/4K3 w - - 0 1");
+    assert_eq!(outpost.outposts[WHITE], (20, 10));
+    // the c pawn can still chase the knight away
+    let attackable = activity("4k3/2p5/8/3N4/4P3/8/8/4K3 w - - 0 1");
+    assert_eq!(attackable.outposts[WHITE], (0, 0));
+    let unsupported = activity("4k3/8/8/3N4/8/8/8/4K3 w - - 0 1");
+    assert_eq!(unsupported.outposts[WHITE], (0, 0));
+    let black_outpost = activity("4k3/8/8/3p4/2b5/8/8/4K3 w - - 0 1");
+    assert_eq!(black_outpost.outposts[BLACK], (10, 5));
+}
+
+#[test]
+fn terms_can_be_weighted_and_turned_off() {
+    let activity = activity("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1");
+    assert_eq!(activity.score(&TermWeights::default()), (-32, -32));
+    let double_mobility = TermWeights {
+        mobility: 200,
+        ..TermWeights::default()
+    };
+    assert_eq!(activity.score(&double_mobility), (-64, -64));
+    let no_mobility = TermWeights {
+        mobility: 0,
+        ..TermWeights::default()
+    };
+    assert_eq!(activity.score(&no_mobility), (0, 0));
+}
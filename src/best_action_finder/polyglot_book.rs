@@ -0,0 +1,252 @@
+#[cfg(test)]
+mod polyglot_book_tests;
+
+use crate::{Action, BoardPosition, BoardState, Piece, PieceColor, PieceType};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// the polyglot format hashes positions with a fixed array of 781 random numbers
+pub const RANDOM64_LEN: usize = 781;
+const CASTLE_OFFSET: usize = 768;
+const EN_PASSANT_OFFSET: usize = 772;
+const TURN_OFFSET: usize = 780;
+// every entry is a big endian key, move, weight and learn value
+const ENTRY_SIZE: usize = 16;
+
+// the Random64 array from the polyglot book format, keys made with any other numbers
+// will not match the ones stored in books
+pub struct PolyglotKeys {
+    random64: Vec<u64>,
+}
+
+impl PolyglotKeys {
+    pub fn new(random64: Vec<u64>) -> Result<PolyglotKeys, PolyglotError> {
+        if random64.len() == RANDOM64_LEN {
+            Ok(PolyglotKeys { random64 })
+        } else {
+            Err(PolyglotError::WrongKeyCount(random64.len()))
+        }
+    }
+
+    // reads every hex number in the text, so the array can be copied straight out of
+    // the c or python source of the format, "0x" prefixes and "ULL" suffixes included
+    pub fn parse(text: &str) -> Result<PolyglotKeys, PolyglotError> {
+        let mut random64 = Vec::with_capacity(RANDOM64_LEN);
+        for token in text.split(|c: char| !c.is_ascii_alphanumeric()) {
+            let digits = if token.starts_with("0x") || token.starts_with("0X") {
+                token[2..].trim_end_matches(|c| "uUlL".contains(c))
+            } else if token.len() == 16 {
+                token
+            } else {
+                continue;
+            };
+            match u64::from_str_radix(digits, 16) {
+                Ok(number) => random64.push(number),
+                Err(_) => return Err(PolyglotError::InvalidKey(token.to_string())),
+            }
+        }
+        PolyglotKeys::new(random64)
+    }
+
+    pub fn load(path: &Path) -> Result<PolyglotKeys, PolyglotError> {
+        PolyglotKeys::parse(&fs::read_to_string(path).map_err(PolyglotError::Io)?)
+    }
+
+    pub fn key(&self, board_state: &BoardState) -> u64 {
+        let mut key = 0;
+        for y in 0..8 {
+            for x in 0..8 {
+                if let Some(piece) = board_state.get(BoardPosition::new(x, y)) {
+                    key ^= self.random64[64 * piece_kind(*piece) + 8 * y as usize + x as usize];
+                }
+            }
+        }
+        let castling = [
+            board_state.white_king_castle,
+            board_state.white_queen_castle,
+            board_state.black_king_castle,
+            board_state.black_queen_castle,
+        ];
+        for (i, can_castle) in castling.iter().enumerate() {
+            if *can_castle {
+                key ^= self.random64[CASTLE_OFFSET + i];
+            }
+        }
+        // the en passant file only counts when a pawn is actually next to the one that moved
+        if board_state.en_passant_colunm < 8 && can_capture_en_passant(board_state) {
+            key ^= self.random64[EN_PASSANT_OFFSET + board_state.en_passant_colunm as usize];
+        }
+        if board_state.color_turn == PieceColor::White {
+            key ^= self.random64[TURN_OFFSET];
+        }
+        key
+    }
+}
+
+// pieces are ordered black pawn, white pawn, black knight, white knight and so on
+fn piece_kind(piece: Piece) -> usize {
+    let type_index = match piece.piece_type {
+        PieceType::Pawn => 0,
+        PieceType::Knight => 1,
+        PieceType::Bishop => 2,
+        PieceType::Rook => 3,
+        PieceType::Queen => 4,
+        PieceType::King => 5,
+    };
+    let color_index = match piece.color {
+        PieceColor::Black => 0,
+        PieceColor::White => 1,
+    };
+    type_index * 2 + color_index
+}
+
+fn can_capture_en_passant(board_state: &BoardState) -> bool {
+    let y = match board_state.color_turn {
+        PieceColor::White => 4,
+        PieceColor::Black => 3,
+    };
+    let pawn = Some(Piece::new(board_state.color_turn, PieceType::Pawn));
+    let x = board_state.en_passant_colunm;
+    (x > 0 && *board_state.get(BoardPosition::new(x - 1, y)) == pawn)
+        || (x < 7 && *board_state.get(BoardPosition::new(x + 1, y)) == pawn)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct BookEntry {
+    key: u64,
+    raw_move: u16,
+    weight: u16,
+}
+
+pub struct PolyglotBook {
+    keys: PolyglotKeys,
+    // sorted by key so all the moves from a position are next to each other
+    entries: Vec<BookEntry>,
+}
+
+impl PolyglotBook {
+    pub fn from_bytes(keys: PolyglotKeys, bytes: &[u8]) -> Result<PolyglotBook, PolyglotError> {
+        if !bytes.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(PolyglotError::InvalidLength(bytes.len()));
+        }
+        let mut entries: Vec<BookEntry> = bytes
+            .chunks(ENTRY_SIZE)
+            .map(|entry| BookEntry {
+                key: read_u64(&entry[0..8]),
+                raw_move: read_u16(&entry[8..10]),
+                weight: read_u16(&entry[10..12]),
+            })
+            .collect();
+        // books should already be sorted but a stable sort keeps the order of equal keys
+        entries.sort_by_key(|entry| entry.key);
+        Ok(PolyglotBook { keys, entries })
+    }
+
+    pub fn load(keys: PolyglotKeys, path: &Path) -> Result<PolyglotBook, PolyglotError> {
+        PolyglotBook::from_bytes(keys, &fs::read(path).map_err(PolyglotError::Io)?)
+    }
+
+    // the legal moves the book has for the position and their weights, entries that
+    // dont decode to a legal move are skipped
+    pub fn find_moves(&self, board_state: &BoardState) -> Vec<(Action, u16)> {
+        let key = self.keys.key(board_state);
+        let mut low = 0;
+        let mut high = self.entries.len();
+        while low < high {
+            let middle = (low + high) / 2;
+            if self.entries[middle].key < key {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+        self.entries[low..]
+            .iter()
+            .take_while(|entry| entry.key == key)
+            .filter_map(|entry| {
+                decode_move(board_state, entry.raw_move).map(|action| (action, entry.weight))
+            })
+            .collect()
+    }
+
+    // picks one of the book moves with a chance proportional to its weight, random is
+    // any number and moves with a weight of zero are never picked
+    pub fn choose_move(&self, board_state: &BoardState, random: u64) -> Option<Action> {
+        let moves = self.find_moves(board_state);
+        let total_weight: u64 = moves.iter().map(|(_, weight)| *weight as u64).sum();
+        if total_weight == 0 {
+            return None;
+        }
+        let mut remaining = random % total_weight;
+        for (action, weight) in moves {
+            if remaining < weight as u64 {
+                return Some(action);
+            }
+            remaining -= weight as u64;
+        }
+        None
+    }
+}
+
+// the move is packed as to file, to row, from file, from row and promotion piece with
+// three bits each, castling is written as the king capturing its own rook
+fn decode_move(board_state: &BoardState, raw_move: u16) -> Option<Action> {
+    let field = |shift: u16| ((raw_move >> shift) & 7) as u8;
+    let from = BoardPosition::new(field(6), field(9));
+    let mut to = BoardPosition::new(field(0), field(3));
+    let promotion = match field(12) {
+        0 => "",
+        1 => "n",
+        2 => "b",
+        3 => "r",
+        4 => "q",
+        _ => return None,
+    };
+    let is_king = board_state.get(from).map(|piece| piece.piece_type) == Some(PieceType::King);
+    if is_king && from.x == 4 && to.y == from.y && (to.x == 0 || to.x == 7) {
+        to = BoardPosition::new(if to.x == 7 { 6 } else { 2 }, to.y);
+    }
+    let uci = format!("{}{}{}", from.to_text(), to.to_text(), promotion);
+    Action::from_uci(board_state, &uci).ok()
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(0, |number, byte| number << 8 | *byte as u64)
+}
+
+fn read_u16(bytes: &[u8]) -> u16 {
+    bytes
+        .iter()
+        .fold(0, |number, byte| number << 8 | *byte as u16)
+}
+
+#[derive(Debug)]
+pub enum PolyglotError {
+    Io(io::Error),
+    InvalidLength(usize),
+    InvalidKey(String),
+    WrongKeyCount(usize),
+}
+
+impl fmt::Display for PolyglotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolyglotError::Io(err) => write!(f, "{}", err),
+            PolyglotError::InvalidLength(length) => write!(
+                f,
+                "book is {} bytes long which is not a whole number of {} byte entries",
+                length, ENTRY_SIZE
+            ),
+            PolyglotError::InvalidKey(key) => write!(f, "'{}' is not a valid random key", key),
+            PolyglotError::WrongKeyCount(count) => write!(
+                f,
+                "expected {} random keys but found {}",
+                RANDOM64_LEN, count
+            ),
+        }
+    }
+}
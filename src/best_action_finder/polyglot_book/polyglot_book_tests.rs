@@ -0,0 +1,179 @@
+use super::{PolyglotBook, PolyglotError, PolyglotKeys, RANDOM64_LEN};
+use crate::{Action, ActionType, BoardPosition, BoardState};
+use std::path::Path;
+
+// made up keys are enough to test the format, real books need the numbers from polyglot
+fn test_keys() -> PolyglotKeys {
+    let random64 = (1..=RANDOM64_LEN as u64)
+        .map(|i| i.wrapping_mul(0x9E37_79B9_7F4A_7C15).rotate_left(17) ^ 0xBF58_476D_1CE4_E5B9)
+        .collect();
+    PolyglotKeys::new(random64).unwrap()
+}
+
+fn raw_move(from: &str, to: &str, promotion: u16) -> u16 {
+    let from = BoardPosition::from_text(from);
+    let to = BoardPosition::from_text(to);
+    promotion << 12 | (from.y as u16) << 9 | (from.x as u16) << 6 | (to.y as u16) << 3 | to.x as u16
+}
+
+fn entry(key: u64, raw_move: u16, weight: u16) -> Vec<u8> {
+    let mut bytes = key.to_be_bytes().to_vec();
+    bytes.extend_from_slice(&raw_move.to_be_bytes());
+    bytes.extend_from_slice(&weight.to_be_bytes());
+    bytes.extend_from_slice(&[0; 4]);
+    bytes
+}
+
+fn simple_move(from: &str, to: &str) -> Action {
+    Action::new(ActionType::SimpleMove {
+        from: BoardPosition::from_text(from),
+        to: BoardPosition::from_text(to),
+    })
+}
+
+#[test]
+fn parse_keys() {
+    let text: Vec<String> = (0..RANDOM64_LEN)
+        .map(|i| format!("U64(0x{:016X}ULL),", i))
+        .collect();
+    assert!(PolyglotKeys::parse(&text.join("\n")).is_ok());
+    assert!(matches!(
+        PolyglotKeys::parse(&text[1..].join(" ")),
+        Err(PolyglotError::WrongKeyCount(780))
+    ));
+    assert!(matches!(
+        PolyglotKeys::parse("0x12345G"),
+        Err(PolyglotError::InvalidKey(_))
+    ));
+}
+
+#[test]
+fn en_passant_only_hashed_when_capture_is_possible() {
+    let keys = test_keys();
+    let key = |fen: &str| keys.key(&BoardState::from_fen(fen).unwrap());
+    assert_eq!(
+        key("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+        key("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+    );
+    assert_ne!(
+        key("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+        key("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+    );
+    assert_ne!(
+        key("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+        key("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1")
+    );
+    assert_ne!(
+        key("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+        key("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Qkq - 0 1")
+    );
+}
+
+#[test]
+fn find_and_choose_moves() {
+    let keys = test_keys();
+    let start = BoardState::default();
+    let castling = BoardState::from_fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+    let start_key = keys.key(&start);
+    let castling_key = keys.key(&castling);
+    let mut bytes = Vec::new();
+    bytes.extend(entry(castling_key, raw_move("e1", "h1", 0), 1));
+    bytes.extend(entry(start_key, raw_move("e2", "e4", 0), 2));
+    bytes.extend(entry(start_key, raw_move("d2", "d5", 0), 5));
+    bytes.extend(entry(start_key, raw_move("d2", "d4", 0), 1));
+    bytes.extend(entry(start_key, raw_move("c2", "c4", 0), 0));
+    let book = PolyglotBook::from_bytes(keys, &bytes).unwrap();
+
+    assert_eq!(
+        book.find_moves(&start),
+        vec![
+            (simple_move("e2", "e4"), 2),
+            (simple_move("d2", "d4"), 1),
+            (simple_move("c2", "c4"), 0)
+        ]
+    );
+    assert_eq!(book.choose_move(&start, 0), Some(simple_move("e2", "e4")));
+    assert_eq!(book.choose_move(&start, 1), Some(simple_move("e2", "e4")));
+    assert_eq!(book.choose_move(&start, 2), Some(simple_move("d2", "d4")));
+    assert_eq!(book.choose_move(&start, 3), Some(simple_move("e2", "e4")));
+    assert_eq!(
+        book.choose_move(&castling, 7),
+        Some(Action::new(ActionType::Castling { kings_side: true }))
+    );
+    let mut after_e4 = start.clone();
+    simple_move("e2", "e4").play_move(&mut after_e4);
+    assert_eq!(book.find_moves(&after_e4), Vec::new());
+    assert_eq!(book.choose_move(&after_e4, 0), None);
+}
+
+#[test]
+fn invalid_book_length() {
+    assert!(matches!(
+        PolyglotBook::from_bytes(test_keys(), &[0; 17]),
+        Err(PolyglotError::InvalidLength(17))
+    ));
+}
+
+#[test]
+fn keys_use_the_spec_offsets() {
+    let keys = test_keys();
+    let key = |fen: &str| keys.key(&BoardState::from_fen(fen).unwrap());
+    let xor = |indices: &[usize]| indices.iter().fold(0, |key, i| key ^ keys.random64[*i]);
+    // pieces are at 64 * kind + 8 * rank + file, with kinds from black pawn = 0 to
+    // white king = 11, castling rights from 768, en passant files from 772 and white
+    // to move at 780
+    assert_eq!(
+        key("4k3/8/8/8/3pP3/8/8/R3K3 b Q e3 0 1"),
+        xor(&[
+            64 * 10 + 8 * 7 + 4, // black king e8
+            64 + 8 * 3 + 4,      // white pawn e4
+            8 * 3 + 3,           // black pawn d4
+            64 * 7,              // white rook a1
+            64 * 11 + 4,         // white king e1
+            768 + 1,             // white can castle queens side
+            772 + 4,             // en passant on the e file
+        ])
+    );
+    assert_eq!(
+        key("r3k3/8/8/8/8/2n5/8/4K2B w q - 0 1"),
+        xor(&[
+            64 * 6 + 8 * 7,      // black rook a8
+            64 * 10 + 8 * 7 + 4, // black king e8
+            64 * 2 + 8 * 2 + 2,  // black knight c3
+            64 * 11 + 4,         // white king e1
+            64 * 5 + 7,          // white bishop h1
+            768 + 3,             // black can castle queens side
+            780,                 // white to move
+        ])
+    );
+}
+
+// the test vectors from the polyglot book format specification, they need the real
+// Random64 numbers in resources/books/polyglot_random64.txt so the test is only run
+// with --ignored once that file has been added
+#[test]
+#[ignore]
+fn published_keys() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("resources/books/polyglot_random64.txt");
+    let keys = PolyglotKeys::load(&path).unwrap();
+    let lines: [(&str, u64); 9] = [
+        ("", 0x463b_9618_1691_fc9c),
+        ("e2e4", 0x823c_9b50_fd11_4196),
+        ("e2e4 d7d5", 0x0756_b944_61c5_0fb0),
+        ("e2e4 d7d5 e4e5", 0x662f_afb9_65db_29d4),
+        ("e2e4 d7d5 e4e5 f7f5", 0x22a4_8b5a_8e47_ff78),
+        ("e2e4 d7d5 e4e5 f7f5 e1e2", 0x652a_607c_a3f2_42c1),
+        ("e2e4 d7d5 e4e5 f7f5 e1e2 e8f7", 0x00fd_d303_c946_bdd9),
+        ("a2a4 b7b5 h2h4 b5b4 c2c4", 0x3c81_23ea_7b06_7637),
+        ("a2a4 b7b5 h2h4 b5b4 c2c4 b4c3 a1a2", 0x5c3f_9b82_9b27_9560),
+    ];
+    for (moves, expected_key) in lines.iter() {
+        let mut board_state = BoardState::default();
+        for uci in moves.split_whitespace() {
+            Action::from_uci(&board_state, uci)
+                .unwrap()
+                .play_move(&mut board_state);
+        }
+        assert_eq!(keys.key(&board_state), *expected_key, "{}", moves);
+    }
+}
@@ -0,0 +1,80 @@
+use super::Score;
+use crate::{BoardState, PieceColor, PieceType};
+
+// the first few actions of a node are always searched to the full depth
+const LATE_MOVE_FULL_DEPTH_ACTIONS: usize = 3;
+const LATE_MOVE_MIN_DEPTH: u8 = 3;
+// actions this late in deep enough nodes are reduced by two plies instead of one
+const LATE_MOVE_DEEP_REDUCTION_ACTIONS: usize = 6;
+const LATE_MOVE_DEEP_REDUCTION_DEPTH: u8 = 6;
+const NULL_MOVE_MIN_DEPTH: u8 = 3;
+// how far a static evaluation can be below alpha and still be raised to it by a quiet
+// action, indexed by the remaining depth
+const FUTILITY_MARGINS: [Score; 3] = [0, 200, 500];
+
+// which of the search's pruning techniques are used, each can be turned off on its own
+// to measure what it is worth
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PruningOptions {
+    pub late_move_reductions: bool,
+    pub null_move_pruning: bool,
+    pub futility_pruning: bool,
+}
+
+impl Default for PruningOptions {
+    fn default() -> PruningOptions {
+        PruningOptions {
+            late_move_reductions: true,
+            null_move_pruning: true,
+            futility_pruning: true,
+        }
+    }
+}
+
+// how many plies less a quiet action is searched given its place in the move ordering,
+// action_number counts from zero
+pub fn late_move_reduction(depth: u8, action_number: usize) -> u8 {
+    if depth < LATE_MOVE_MIN_DEPTH || action_number < LATE_MOVE_FULL_DEPTH_ACTIONS {
+        0
+    } else if depth >= LATE_MOVE_DEEP_REDUCTION_DEPTH
+        && action_number >= LATE_MOVE_DEEP_REDUCTION_ACTIONS
+    {
+        2
+    } else {
+        1
+    }
+}
+
+// the depth taken off the search after a null move, on top of the ply for the move itself
+pub fn null_move_reduction(depth: u8) -> u8 {
+    if depth > 6 {
+        3
+    } else {
+        2
+    }
+}
+
+// a null move is only tried when passing is unlikely to be the best thing to do, which is
+// not true in zugzwang, and zugzwang is mostly found in endings with only kings and pawns
+pub fn null_move_allowed(board_state: &BoardState, depth: u8) -> bool {
+    depth >= NULL_MOVE_MIN_DEPTH && has_non_pawn_material(board_state, board_state.color_turn)
+}
+
+// the margin for futility pruning at this depth, None when it is too deep to prune
+pub fn futility_margin(depth: u8) -> Option<Score> {
+    match depth {
+        0 => None,
+        _ => FUTILITY_MARGINS.get(depth as usize).copied(),
+    }
+}
+
+fn has_non_pawn_material(board_state: &BoardState, color: PieceColor) -> bool {
+    [
+        PieceType::Knight,
+        PieceType::Bishop,
+        PieceType::Rook,
+        PieceType::Queen,
+    ]
+    .iter()
+    .any(|piece_type| board_state.piece_bitboard(color, *piece_type) != 0)
+}
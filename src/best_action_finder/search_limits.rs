@@ -0,0 +1,62 @@
+#[cfg(test)]
+mod search_limits_tests;
+
+use std::time::Duration;
+
+pub const DEFAULT_DEPTH: u8 = 7;
+// deep enough that a search to this depth will always be stopped by some other limit first
+pub const MAX_DEPTH: u8 = 64;
+// when playing on a clock the engine assumes this many moves are left in the game
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+// time kept in reserve so the engine does not lose on time because of communication delays
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+
+#[derive(Copy, Clone, Debug)]
+pub struct Clock {
+    pub time_left: Duration,
+    pub increment: Duration,
+    pub moves_to_go: Option<u32>,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct SearchLimits {
+    pub max_depth: u8,
+    pub max_nodes: Option<u64>,
+    pub move_time: Option<Duration>,
+    pub clock: Option<Clock>,
+}
+
+impl SearchLimits {
+    // the time the search must stop by, if there is one
+    pub fn allocated_time(&self) -> Option<Duration> {
+        if let Some(move_time) = self.move_time {
+            Some(sub_or_zero(move_time, MOVE_OVERHEAD))
+        } else if let Some(clock) = self.clock {
+            let moves_to_go = clock.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+            let budget = clock.time_left / moves_to_go + clock.increment;
+            let max_budget = sub_or_zero(clock.time_left, MOVE_OVERHEAD);
+            Some(sub_or_zero(budget.min(max_budget), MOVE_OVERHEAD))
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for SearchLimits {
+    fn default() -> SearchLimits {
+        SearchLimits {
+            max_depth: DEFAULT_DEPTH,
+            max_nodes: None,
+            move_time: None,
+            clock: None,
+        }
+    }
+}
+
+fn sub_or_zero(a: Duration, b: Duration) -> Duration {
+    if a > b {
+        a - b
+    } else {
+        Duration::from_millis(0)
+    }
+}
@@ -0,0 +1,60 @@
+use super::{Clock, SearchLimits};
+use std::time::Duration;
+
+fn clock_limits(time_left_ms: u64, increment_ms: u64, moves_to_go: Option<u32>) -> SearchLimits {
+    SearchLimits {
+        clock: Some(Clock {
+            time_left: Duration::from_millis(time_left_ms),
+            increment: Duration::from_millis(increment_ms),
+            moves_to_go,
+        }),
+        ..SearchLimits::default()
+    }
+}
+
+#[test]
+fn no_time_limit() {
+    assert_eq!(SearchLimits::default().allocated_time(), None);
+}
+
+#[test]
+fn time_from_move_time() {
+    let limits = SearchLimits {
+        move_time: Some(Duration::from_millis(1000)),
+        ..SearchLimits::default()
+    };
+    assert_eq!(limits.allocated_time(), Some(Duration::from_millis(950)));
+    // the move time is used over the clock when both are given
+    let limits = SearchLimits {
+        move_time: Some(Duration::from_millis(1000)),
+        ..clock_limits(60_000, 1000, None)
+    };
+    assert_eq!(limits.allocated_time(), Some(Duration::from_millis(950)));
+    let limits = SearchLimits {
+        move_time: Some(Duration::from_millis(20)),
+        ..SearchLimits::default()
+    };
+    assert_eq!(limits.allocated_time(), Some(Duration::from_millis(0)));
+}
+
+#[test]
+fn time_from_clock_and_increment() {
+    // a thirtieth of the time left plus the increment, less the overhead
+    assert_eq!(
+        clock_limits(60_000, 1000, None).allocated_time(),
+        Some(Duration::from_millis(2950))
+    );
+    assert_eq!(
+        clock_limits(60_000, 0, Some(10)).allocated_time(),
+        Some(Duration::from_millis(5950))
+    );
+    // the increment can't be spent before it has been added to the clock
+    assert_eq!(
+        clock_limits(1000, 2000, Some(1)).allocated_time(),
+        Some(Duration::from_millis(900))
+    );
+    assert_eq!(
+        clock_limits(30, 2000, None).allocated_time(),
+        Some(Duration::from_millis(0))
+    );
+}
@@ -0,0 +1,284 @@
+use super::endgame_table_search::EndgameTableSearcher;
+use super::minimax::Minimax;
+use super::transposition_table::TranspositionTable;
+use super::{Evaluator, PruningOptions, SearchLimits, SearchProgress, SearchResult, MAX_DEPTH};
+use crate::{find_legal_actions, Action, ActionType, BoardPosition, BoardState, PieceColor};
+use std::sync::atomic::AtomicBool;
+
+// positions from the win at chess test suite in epd format with their best moves
+const TACTICAL_SUITE: [&str; 20] = [
+    "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm g3g6",
+    "8/7p/5k2/5p2/p1p2P2/Pr1pPK2/1P1R3P/8 b - - bm b3b2",
+    "5rk1/1ppb3p/p1pb4/6q1/3P1p1r/2P1R2P/PP1BQ1P1/5RKN w - - bm e3g3",
+    "r1bq2rk/pp3pbp/2p1p1pQ/7P/3P4/2PB1N2/PP3PPR/2KR4 w - - bm h6h7",
+    "5k2/6pp/p1qN4/1p1p4/3P4/2PKP2Q/PP3r2/3R4 b - - bm c6c4",
+    "7k/p7/1R5K/6r1/6p1/6P1/8/8 w - - bm b6b7",
+    "rnbqkb1r/pppp1ppp/8/4P3/6n1/7P/PPPNPPP1/R1BQKBNR b KQkq - bm g4e3",
+    "r4q1k/p2bR1rp/2p2Q1N/5p2/5p2/2P5/PP3PPP/R5K1 w - - bm e7f7",
+    "3q1rk1/p4pp1/2pb3p/3p4/6Pr/1PNQ4/P1PB1PP1/4RRK1 b - - bm d6h2",
+    "2br2k1/2q3rn/p2NppQ1/2p1P3/Pp5R/4P3/1P3PPP/3R2K1 w - - bm h4h7",
+    "r1b1kb1r/3q1ppp/pBp1pn2/8/Np3P2/5B2/PPP3PP/R2Q1RK1 w kq - bm f3c6",
+    "4k1r1/2p3r1/1pR1p3/3pP2p/3P2qP/P4N2/1PQ4P/5R1K b - - bm g4f3",
+    "5rk1/pp4p1/2n1p2p/2Npq3/2p5/6P1/P3P1BP/R4Q1K w - - bm f1f8",
+    "r2rb1k1/pp1q1p1p/2n1p1p1/2bp4/5P2/PP1BPR1Q/1BPN2PP/R5K1 w - - bm h3h7",
+    "1R6/1brk2p1/4p2p/p1P1Pp2/P7/6P1/1P4P1/2R3K1 w - - bm b8b7",
+    "r4rk1/ppp2ppp/2n5/2bqp3/8/P2PB3/1PP1NPPP/R2Q1RK1 w - - bm e2c3",
+    "1k5r/pppbn1pp/4q1r1/1P3p2/2NPp3/1QP5/P4PPP/R1B1R1K1 w - - bm c4e5",
+    "R7/P4k2/8/8/8/8/r7/6K1 w - - bm a8h8",
+    "r1b2rk1/ppbn1ppp/4p3/1QP4q/3P4/N4N2/5PPP/R1B2RK1 w - - bm c5c6",
+    "r2qkb1r/1ppb1ppp/p7/4p3/P1Q1P3/2P5/5PPP/R1B2KNR b kq - bm d7b5",
+];
+// the number of suite positions that must be solved at the test depth
+const TACTICAL_SUITE_MIN_SOLVED: usize = 16;
+const TACTICAL_SUITE_DEPTH: u8 = 4;
+
+fn search(evaluator: &Evaluator, fen: &str, depth: u8) -> SearchResult {
+    search_with_pruning(evaluator, fen, depth, PruningOptions::default())
+}
+
+fn search_with_pruning(
+    evaluator: &Evaluator,
+    fen: &str,
+    depth: u8,
+    pruning: PruningOptions,
+) -> SearchResult {
+    let board_state = BoardState::from_fen(fen).unwrap();
+    let stop_signal = AtomicBool::new(false);
+    let mut transposition_table = TranspositionTable::new(1);
+    let limits = SearchLimits {
+        max_depth: depth,
+        ..SearchLimits::default()
+    };
+    let mut minimax = Minimax::new(limits, evaluator, &stop_signal, &mut transposition_table);
+    minimax.set_pruning_options(pruning);
+    minimax
+        .find_maximising_move(&board_state, &mut |_| ())
+        .unwrap()
+}
+
+fn find_best_action(evaluator: &Evaluator, fen: &str, depth: u8) -> Action {
+    search(evaluator, fen, depth).action
+}
+
+// compares the squares an action moves between with a move like "e2e4"
+fn moves_between(action: Action, move_text: &str) -> bool {
+    let (from_text, to_text) = move_text.split_at(2);
+    let squares = (
+        BoardPosition::from_text(from_text),
+        BoardPosition::from_text(to_text),
+    );
+    match action.get_action_type() {
+        ActionType::SimpleMove { from, to }
+        | ActionType::EnPassant { from, to }
+        | ActionType::Promotion {
+            from,
+            to,
+            piece_type: _,
+        } => (from, to) == squares,
+        ActionType::Castling { kings_side: _ } => false,
+    }
+}
+
+#[test]
+fn tactical_suite() {
+    let evaluator = Evaluator::new(EndgameTableSearcher::new());
+    let solved = TACTICAL_SUITE
+        .iter()
+        .filter(|epd| {
+            let (fen, best_move) = epd.split_at(epd.find(" bm ").unwrap());
+            let best_move = &best_move[" bm ".len()..];
+            let action = find_best_action(&evaluator, fen, TACTICAL_SUITE_DEPTH);
+            moves_between(action, best_move)
+        })
+        .count();
+    assert!(
+        solved >= TACTICAL_SUITE_MIN_SOLVED,
+        "only solved {} of {} positions",
+        solved,
+        TACTICAL_SUITE.len()
+    );
+}
+
+#[test]
+fn quiescence_sees_recapture() {
+    // the pawn on d5 is defended so taking it loses the queen
+    let evaluator = Evaluator::new(EndgameTableSearcher::new());
+    let fen = "r3k3/pp6/2p5/3p4/8/8/PP1Q4/4K2R w K - 0 1";
+    for depth in 1..=3 {
+        let action = find_best_action(&evaluator, fen, depth);
+        assert!(!moves_between(action, "d2d5"), "depth {}", depth);
+    }
+}
+
+#[test]
+fn principal_variation_is_legal_line() {
+    let evaluator = Evaluator::new(EndgameTableSearcher::new());
+    for fen in [
+        crate::STARTING_FEN,
+        "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+    ]
+    .iter()
+    {
+        let result = search(&evaluator, fen, 4);
+        assert_eq!(result.pv[0], result.action);
+        assert!(result.pv.len() >= 2, "{}", fen);
+        let mut board_state = BoardState::from_fen(fen).unwrap();
+        for action in result.pv.iter() {
+            assert!(find_legal_actions(&board_state).0.contains(action));
+            action.play_move(&mut board_state);
+        }
+    }
+}
+
+#[test]
+fn principal_variation_ends_in_mate() {
+    let evaluator = Evaluator::new(EndgameTableSearcher::new());
+    let fen = "6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1";
+    let result = search(&evaluator, fen, 3);
+    let board_state = BoardState::from_fen(fen).unwrap();
+    assert_eq!(result.pv_to_san(&board_state), "Ra8#");
+    assert_eq!(result.pv_to_uci(&board_state), "a1a8");
+}
+
+#[test]
+fn finds_mate_in_two() {
+    // one rook is given up on the back rank and the other takes back with mate
+    let evaluator = Evaluator::new(EndgameTableSearcher::new());
+    let fen = "2r3k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1";
+    let result = search(&evaluator, fen, 4);
+    assert!(moves_between(result.action, "d2d8"));
+    // white mates on the third ply
+    assert_eq!(
+        result.score,
+        evaluator.score_for_checkmate(PieceColor::White, 3)
+    );
+}
+
+#[test]
+fn mate_scores_from_the_transposition_table_count_from_the_root() {
+    let evaluator = Evaluator::new(EndgameTableSearcher::new());
+    let stop_signal = AtomicBool::new(false);
+    let mut transposition_table = TranspositionTable::new(1);
+    let mut search = |fen: &str, depth: u8| {
+        let limits = SearchLimits {
+            max_depth: depth,
+            ..SearchLimits::default()
+        };
+        Minimax::new(limits, &evaluator, &stop_signal, &mut transposition_table)
+            .find_maximising_move(&BoardState::from_fen(fen).unwrap(), &mut |_| ())
+            .unwrap()
+    };
+    // a ladder mate in one, stored at the root of this search
+    search("5k2/1R6/8/6B1/8/8/R4PPP/6K1 w - - 2 2", 2);
+    // the position a move earlier finds it a ply further from the root
+    let result = search("4k3/1R6/8/6B1/8/8/R4PPP/6K1 b - - 1 1", 2);
+    assert_eq!(
+        result.score,
+        evaluator.score_for_checkmate(PieceColor::White, 2)
+    );
+}
+
+#[test]
+fn each_pruning_technique_can_be_turned_off() {
+    let evaluator = Evaluator::new(EndgameTableSearcher::new());
+    let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4";
+    let none = PruningOptions {
+        late_move_reductions: false,
+        null_move_pruning: false,
+        futility_pruning: false,
+    };
+    let all_nodes = search(&evaluator, fen, 5).nodes;
+    let options = [
+        PruningOptions {
+            late_move_reductions: true,
+            ..none
+        },
+        PruningOptions {
+            null_move_pruning: true,
+            ..none
+        },
+        PruningOptions {
+            futility_pruning: true,
+            ..none
+        },
+    ];
+    let none_nodes = search_with_pruning(&evaluator, fen, 5, none).nodes;
+    for pruning in options.iter() {
+        let nodes = search_with_pruning(&evaluator, fen, 5, *pruning).nodes;
+        assert!(nodes < none_nodes, "{:?}", pruning);
+        // the tactics are still found with only one technique
+        let mate = search_with_pruning(
+            &evaluator,
+            "6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1",
+            4,
+            *pruning,
+        );
+        assert!(moves_between(mate.action, "a1a8"), "{:?}", pruning);
+    }
+    assert!(all_nodes < none_nodes);
+}
+
+#[test]
+fn score_is_from_blacks_side_for_both_colors() {
+    // white is a queen up whoever is to move
+    let evaluator = Evaluator::new(EndgameTableSearcher::new());
+    for fen in [
+        "4k3/pppppppp/8/8/8/8/PPPPPPPP/3QK3 w - - 0 1",
+        "4k3/pppppppp/8/8/8/8/PPPPPPPP/3QK3 b - - 0 1",
+    ]
+    .iter()
+    {
+        let result = search(&evaluator, fen, 5);
+        assert!(result.score < -500, "{} {}", fen, result.score);
+    }
+}
+
+#[test]
+fn mirrored_positions_have_opposite_scores() {
+    let evaluator = Evaluator::new(EndgameTableSearcher::new());
+    let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4";
+    let mirrored = "rnbqk2r/pppp1ppp/5n2/2b1p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R b KQkq - 4 4";
+    for depth in 1..=4 {
+        let score = search(&evaluator, fen, depth).score;
+        let mirrored_score = search(&evaluator, mirrored, depth).score;
+        assert_eq!(score, -mirrored_score, "depth {}", depth);
+    }
+}
+
+#[test]
+fn node_limit_aborts_the_search() {
+    let evaluator = Evaluator::new(EndgameTableSearcher::new());
+    let board_state =
+        BoardState::from_fen(TACTICAL_SUITE[0].split(" bm ").next().unwrap()).unwrap();
+    let stop_signal = AtomicBool::new(false);
+    let mut transposition_table = TranspositionTable::new(1);
+    let limits = SearchLimits {
+        max_depth: MAX_DEPTH,
+        max_nodes: Some(20_000),
+        ..SearchLimits::default()
+    };
+    let mut completed_iterations = Vec::new();
+    let mut minimax = Minimax::new(limits, &evaluator, &stop_signal, &mut transposition_table);
+    let result = minimax
+        .find_maximising_move(&board_state, &mut |progress| {
+            if let SearchProgress::Iteration(result) = progress {
+                completed_iterations.push(result.clone());
+            }
+        })
+        .unwrap();
+    // the search stops soon after the limit is reached rather than at the max depth
+    assert!(
+        result.nodes >= 20_000 && result.nodes < 21_000,
+        "{}",
+        result.nodes
+    );
+    assert!(result.depth < MAX_DEPTH);
+
+    // the unfinished iteration is thrown away for the last one that was completed
+    let last_completed = completed_iterations.last().unwrap();
+    assert_eq!(result.depth, last_completed.depth);
+    assert_eq!(result.action, last_completed.action);
+    assert_eq!(result.pv, last_completed.pv);
+    assert_eq!(result.score, last_completed.score);
+    assert!(result.nodes > last_completed.nodes);
+}
@@ -0,0 +1,82 @@
+#[cfg(test)]
+mod transposition_table_tests;
+
+use super::Score;
+use crate::Action;
+use std::mem;
+
+pub const DEFAULT_SIZE_MB: usize = 16;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Bound {
+    Exact,
+    // the real score is at least the stored score
+    Lower,
+    // the real score is at most the stored score
+    Upper,
+}
+
+#[derive(Copy, Clone)]
+pub struct TranspositionEntry {
+    key: u64,
+    pub depth: u8,
+    pub bound: Bound,
+    pub score: Score,
+    pub best_action: Option<Action>,
+}
+
+pub struct TranspositionTable {
+    entries: Vec<Option<TranspositionEntry>>,
+}
+
+impl TranspositionTable {
+    pub fn new(size_mb: usize) -> TranspositionTable {
+        let max_entries =
+            size_mb.max(1) * 1024 * 1024 / mem::size_of::<Option<TranspositionEntry>>();
+        // a power of two number of entries lets the index be found with a mask
+        let mut entry_count = 1;
+        while entry_count * 2 <= max_entries {
+            entry_count *= 2;
+        }
+        TranspositionTable {
+            entries: vec![None; entry_count],
+        }
+    }
+    pub fn clear(&mut self) {
+        for entry in self.entries.iter_mut() {
+            *entry = None;
+        }
+    }
+    fn index(&self, key: u64) -> usize {
+        (key as usize) & (self.entries.len() - 1)
+    }
+    pub fn probe(&self, key: u64) -> Option<TranspositionEntry> {
+        match self.entries[self.index(key)] {
+            Some(entry) if entry.key == key => Some(entry),
+            _ => None,
+        }
+    }
+    pub fn store(
+        &mut self,
+        key: u64,
+        depth: u8,
+        bound: Bound,
+        score: Score,
+        best_action: Option<Action>,
+    ) {
+        let index = self.index(key);
+        // entries for the same position are only replaced by deeper searches
+        if let Some(existing) = self.entries[index] {
+            if existing.key == key && existing.depth > depth {
+                return;
+            }
+        }
+        self.entries[index] = Some(TranspositionEntry {
+            key,
+            depth,
+            bound,
+            score,
+            best_action,
+        });
+    }
+}
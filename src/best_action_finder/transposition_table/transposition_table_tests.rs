@@ -0,0 +1,81 @@
+use super::{Bound, TranspositionEntry, TranspositionTable};
+use crate::{find_legal_actions, BoardState};
+use std::mem;
+
+const KEY: u64 = 0x1234_5678_9abc_def0;
+
+#[test]
+fn store_and_probe() {
+    let mut table = TranspositionTable::new(1);
+    let action = find_legal_actions(&BoardState::default()).0[0];
+    assert!(table.probe(KEY).is_none());
+    table.store(KEY, 4, Bound::Exact, 35, Some(action));
+    let entry = table.probe(KEY).unwrap();
+    assert_eq!(entry.depth, 4);
+    assert_eq!(entry.bound, Bound::Exact);
+    assert_eq!(entry.score, 35);
+    assert_eq!(entry.best_action, Some(action));
+    assert!(table.probe(KEY + 1).is_none());
+    // a different position in the same slot is not mistaken for this one
+    let colliding_key = KEY + table.entries.len() as u64;
+    assert!(table.probe(colliding_key).is_none());
+
+    table.clear();
+    assert!(table.probe(KEY).is_none());
+}
+
+#[test]
+fn bounds_are_kept() {
+    let mut table = TranspositionTable::new(1);
+    for (i, bound) in [Bound::Exact, Bound::Lower, Bound::Upper]
+        .iter()
+        .enumerate()
+    {
+        table.store(KEY + i as u64, 3, *bound, -120, None);
+    }
+    for (i, bound) in [Bound::Exact, Bound::Lower, Bound::Upper]
+        .iter()
+        .enumerate()
+    {
+        let entry = table.probe(KEY + i as u64).unwrap();
+        assert_eq!(entry.bound, *bound);
+        assert_eq!(entry.score, -120);
+        assert_eq!(entry.best_action, None);
+    }
+}
+
+#[test]
+fn deeper_entries_are_kept() {
+    let mut table = TranspositionTable::new(1);
+    table.store(KEY, 5, Bound::Exact, 10, None);
+    table.store(KEY, 3, Bound::Lower, 20, None);
+    assert_eq!(table.probe(KEY).unwrap().score, 10);
+    // a search as deep as the stored one is more recent so it replaces it
+    table.store(KEY, 5, Bound::Upper, 30, None);
+    let entry = table.probe(KEY).unwrap();
+    assert_eq!(
+        (entry.depth, entry.bound, entry.score),
+        (5, Bound::Upper, 30)
+    );
+    table.store(KEY, 7, Bound::Exact, 40, None);
+    assert_eq!(table.probe(KEY).unwrap().depth, 7);
+
+    // another position in the same slot always replaces the entry
+    let colliding_key = KEY + table.entries.len() as u64;
+    table.store(colliding_key, 1, Bound::Exact, 50, None);
+    assert!(table.probe(KEY).is_none());
+    assert_eq!(table.probe(colliding_key).unwrap().score, 50);
+}
+
+#[test]
+fn size_is_a_power_of_two_within_the_limit() {
+    let entry_size = mem::size_of::<Option<TranspositionEntry>>();
+    for size_mb in [0, 1, 3, 16].iter() {
+        let table = TranspositionTable::new(*size_mb);
+        let entry_count = table.entries.len();
+        let max_bytes = (*size_mb).max(1) * 1024 * 1024;
+        assert!(entry_count.is_power_of_two(), "{} MB", size_mb);
+        assert!(entry_count * entry_size <= max_bytes, "{} MB", size_mb);
+        assert!(entry_count * 2 * entry_size > max_bytes, "{} MB", size_mb);
+    }
+}
@@ -1,5 +1,13 @@
+pub mod bitboard;
+#[cfg(test)]
+mod board_state_tests;
+mod zobrist;
+
+use bitboard::{position_bitboard, Bitboard};
 use std::fmt;
-use std::hash::Hash;
+use std::hash::{Hash, Hasher};
+
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
 pub trait Capturable {
     fn can_take(&self, color: PieceColor) -> bool;
@@ -15,7 +23,7 @@ impl Capturable for Option<Piece> {
     }
 }
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, PartialEq, Eq)]
 pub struct BoardPosition {
     pub x: u8,
     pub y: u8,
@@ -53,9 +61,19 @@ impl BoardPosition {
     pub fn new(x: u8, y: u8) -> BoardPosition {
         BoardPosition { x: x, y: y }
     }
+    // the index of the position's bit in a bitboard
+    pub fn to_index(&self) -> u8 {
+        self.y * 8 + self.x
+    }
+    pub fn from_index(index: u8) -> BoardPosition {
+        BoardPosition::new(index % 8, index / 8)
+    }
     pub fn from_text(string: &str) -> BoardPosition {
-        assert!(string.len() == 2);
-        let x = match string.chars().nth(0).unwrap() {
+        BoardPosition::try_from_text(string).expect("invalid board position string")
+    }
+    pub fn try_from_text(string: &str) -> Option<BoardPosition> {
+        let mut chars = string.chars();
+        let x = match chars.next()? {
             'a' => 0,
             'b' => 1,
             'c' => 2,
@@ -64,9 +82,9 @@ impl BoardPosition {
             'f' => 5,
             'g' => 6,
             'h' => 7,
-            _ => panic!("invalid board position string"),
+            _ => return None,
         };
-        let y = match string.chars().nth(1).unwrap() {
+        let y = match chars.next()? {
             '1' => 0,
             '2' => 1,
             '3' => 2,
@@ -75,9 +93,12 @@ impl BoardPosition {
             '6' => 5,
             '7' => 6,
             '8' => 7,
-            _ => panic!("invalid board position string"),
+            _ => return None,
         };
-        BoardPosition { x: x, y: y }
+        if chars.next().is_some() {
+            return None;
+        }
+        Some(BoardPosition { x, y })
     }
     pub fn to_text(&self) -> String {
         let file = match self.x {
@@ -137,7 +158,7 @@ impl PieceColor {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, PartialEq, Debug, Eq, Hash)]
 pub enum PieceType {
     Pawn,
     Bishop,
@@ -158,20 +179,26 @@ impl Piece {
         Piece { color, piece_type }
     }
     pub fn from_fen_char(c: char) -> Piece {
+        match Piece::try_from_fen_char(c) {
+            Some(piece) => piece,
+            None => panic!("cant parse char '{}' as piece", c),
+        }
+    }
+    pub fn try_from_fen_char(c: char) -> Option<Piece> {
         match c {
-            'p' => Piece::new(PieceColor::Black, PieceType::Pawn),
-            'n' => Piece::new(PieceColor::Black, PieceType::Knight),
-            'b' => Piece::new(PieceColor::Black, PieceType::Bishop),
-            'r' => Piece::new(PieceColor::Black, PieceType::Rook),
-            'q' => Piece::new(PieceColor::Black, PieceType::Queen),
-            'k' => Piece::new(PieceColor::Black, PieceType::King),
-            'P' => Piece::new(PieceColor::White, PieceType::Pawn),
-            'N' => Piece::new(PieceColor::White, PieceType::Knight),
-            'B' => Piece::new(PieceColor::White, PieceType::Bishop),
-            'R' => Piece::new(PieceColor::White, PieceType::Rook),
-            'Q' => Piece::new(PieceColor::White, PieceType::Queen),
-            'K' => Piece::new(PieceColor::White, PieceType::King),
-            _ => panic!("cant parse char '{}' as piece", c),
+            'p' => Some(Piece::new(PieceColor::Black, PieceType::Pawn)),
+            'n' => Some(Piece::new(PieceColor::Black, PieceType::Knight)),
+            'b' => Some(Piece::new(PieceColor::Black, PieceType::Bishop)),
+            'r' => Some(Piece::new(PieceColor::Black, PieceType::Rook)),
+            'q' => Some(Piece::new(PieceColor::Black, PieceType::Queen)),
+            'k' => Some(Piece::new(PieceColor::Black, PieceType::King)),
+            'P' => Some(Piece::new(PieceColor::White, PieceType::Pawn)),
+            'N' => Some(Piece::new(PieceColor::White, PieceType::Knight)),
+            'B' => Some(Piece::new(PieceColor::White, PieceType::Bishop)),
+            'R' => Some(Piece::new(PieceColor::White, PieceType::Rook)),
+            'Q' => Some(Piece::new(PieceColor::White, PieceType::Queen)),
+            'K' => Some(Piece::new(PieceColor::White, PieceType::King)),
+            _ => None,
         }
     }
     pub fn to_unicode_char(&self) -> char {
@@ -216,15 +243,26 @@ impl Piece {
     }
 }
 
-#[derive(Clone, Eq, Hash)]
+#[derive(Clone, Eq)]
 pub struct BoardState {
     pieces: [[Option<Piece>; 8]; 8],
+    // the same pieces as bitboards indexed by color then piece type, kept in sync by set
+    piece_bitboards: [[Bitboard; 6]; 2],
+    color_bitboards: [Bitboard; 2],
     pub white_king_castle: bool,
     pub white_queen_castle: bool,
     pub black_king_castle: bool,
     pub black_queen_castle: bool,
     pub en_passant_colunm: u8,
     pub color_turn: PieceColor,
+    // number of halfmoves since the last capture or pawn move, used for the fifty-move rule
+    pub halfmove_clock: u16,
+    // starts at 1 and is incremented after each black move
+    pub fullmove_number: u16,
+    // zobrist keys of the positions since the last capture or pawn move, earlier positions can never be repeated
+    position_history: Vec<u64>,
+    // updated incrementally as pieces are set and moves are played
+    zobrist_key: u64,
 }
 
 impl PartialEq for BoardState {
@@ -240,6 +278,7 @@ impl PartialEq for BoardState {
         if self.white_king_castle != other.white_king_castle
             || self.black_king_castle != other.black_king_castle
             || self.white_queen_castle != other.white_queen_castle
+            || self.black_queen_castle != other.black_queen_castle
             || self.color_turn != other.color_turn
         {
             return false;
@@ -252,12 +291,51 @@ impl PartialEq for BoardState {
     }
 }
 
+// the move history and clocks are not hashed so that the same position reached
+// in different ways is treated as the same board state
+impl Hash for BoardState {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.pieces.hash(state);
+        self.white_king_castle.hash(state);
+        self.white_queen_castle.hash(state);
+        self.black_king_castle.hash(state);
+        self.black_queen_castle.hash(state);
+        self.color_turn.hash(state);
+    }
+}
+
 impl BoardState {
     pub fn get(&self, pos: BoardPosition) -> &Option<Piece> {
         &self.pieces[pos.y as usize][pos.x as usize]
     }
-    pub fn get_mut(&mut self, pos: BoardPosition) -> &mut Option<Piece> {
-        &mut self.pieces[pos.y as usize][pos.x as usize]
+    pub fn set(&mut self, pos: BoardPosition, piece: Option<Piece>) {
+        let square = &mut self.pieces[pos.y as usize][pos.x as usize];
+        let pos_bitboard = position_bitboard(pos);
+        if let Some(old_piece) = *square {
+            self.zobrist_key ^= zobrist::piece_key(old_piece, pos);
+            self.piece_bitboards[old_piece.color as usize][old_piece.piece_type as usize] &=
+                !pos_bitboard;
+            self.color_bitboards[old_piece.color as usize] &= !pos_bitboard;
+        }
+        if let Some(new_piece) = piece {
+            self.zobrist_key ^= zobrist::piece_key(new_piece, pos);
+            self.piece_bitboards[new_piece.color as usize][new_piece.piece_type as usize] |=
+                pos_bitboard;
+            self.color_bitboards[new_piece.color as usize] |= pos_bitboard;
+        }
+        *square = piece;
+    }
+    pub fn piece_bitboard(&self, color: PieceColor, piece_type: PieceType) -> Bitboard {
+        self.piece_bitboards[color as usize][piece_type as usize]
+    }
+    pub fn color_bitboard(&self, color: PieceColor) -> Bitboard {
+        self.color_bitboards[color as usize]
+    }
+    pub fn occupied_bitboard(&self) -> Bitboard {
+        self.color_bitboards[0] | self.color_bitboards[1]
+    }
+    pub fn king_position(&self, color: PieceColor) -> Option<BoardPosition> {
+        bitboard::squares(self.piece_bitboard(color, PieceType::King)).next()
     }
     pub fn alpha_to_move(&self) -> bool {
         match self.color_turn {
@@ -265,27 +343,123 @@ impl BoardState {
             PieceColor::Black => true,
         }
     }
+    pub fn zobrist_key(&self) -> u64 {
+        self.zobrist_key
+    }
+    // the part of the zobrist key that doesnt depend on the pieces
+    pub fn state_key(&self) -> u64 {
+        let mut key = 0;
+        let castling_rights = [
+            self.white_king_castle,
+            self.white_queen_castle,
+            self.black_king_castle,
+            self.black_queen_castle,
+        ];
+        for (i, can_castle) in castling_rights.iter().enumerate() {
+            if *can_castle {
+                key ^= zobrist::castling_key(i);
+            }
+        }
+        if self.en_passant_colunm < 8 {
+            key ^= zobrist::en_passant_key(self.en_passant_colunm);
+        }
+        if self.color_turn == PieceColor::Black {
+            key ^= zobrist::black_to_move_key();
+        }
+        key
+    }
+    // must be called after the castling rights, en passant colunm or color turn are changed
+    pub fn update_state_key(&mut self, previous_state_key: u64) {
+        self.zobrist_key ^= previous_state_key ^ self.state_key();
+    }
+    fn calculate_zobrist_key(&self) -> u64 {
+        let mut key = self.state_key();
+        for y in 0..8 {
+            for x in 0..8 {
+                let pos = BoardPosition::new(x, y);
+                if let Some(piece) = self.get(pos) {
+                    key ^= zobrist::piece_key(*piece, pos);
+                }
+            }
+        }
+        key
+    }
+    // must be called before a move is played on this board state
+    pub fn record_move(&mut self, irreversible: bool) {
+        if self.color_turn == PieceColor::Black {
+            self.fullmove_number += 1;
+        }
+        if irreversible {
+            self.halfmove_clock = 0;
+            self.position_history.clear();
+        } else {
+            self.halfmove_clock += 1;
+            self.position_history.push(self.zobrist_key);
+        }
+    }
+    // passes the turn to the other color without moving, it is treated as irreversible so
+    // no repetition is ever found across it
+    pub fn play_null_move(&mut self) {
+        self.record_move(true);
+        let previous_state_key = self.state_key();
+        self.en_passant_colunm = 55;
+        self.color_turn = self.color_turn.opposite_color();
+        self.update_state_key(previous_state_key);
+    }
+    // the number of times the current position has occurred before
+    pub fn repetition_count(&self) -> usize {
+        self.position_history
+            .iter()
+            .filter(|previous_key| **previous_key == self.zobrist_key)
+            .count()
+    }
     pub fn to_fen(&self) -> String {
-        let mut board_string = String::with_capacity(62);
+        let mut board_string = String::with_capacity(71);
         for y in (0..8).rev() {
+            let mut empty_squares = 0;
             for x in 0..8 {
-                let piece_option = self.get(BoardPosition::new(x, y));
-                board_string.push(match piece_option {
-                    None => '1',
-                    Some(piece) => piece.to_fen_char(),
-                })
+                match self.get(BoardPosition::new(x, y)) {
+                    None => empty_squares += 1,
+                    Some(piece) => {
+                        if empty_squares != 0 {
+                            board_string.push_str(&empty_squares.to_string());
+                            empty_squares = 0;
+                        }
+                        board_string.push(piece.to_fen_char());
+                    }
+                }
+            }
+            if empty_squares != 0 {
+                board_string.push_str(&empty_squares.to_string());
             }
             if y != 0 {
                 board_string.push('/');
             }
         }
+        let mut castling_string = String::with_capacity(4);
+        if self.white_king_castle {
+            castling_string.push('K');
+        }
+        if self.white_queen_castle {
+            castling_string.push('Q');
+        }
+        if self.black_king_castle {
+            castling_string.push('k');
+        }
+        if self.black_queen_castle {
+            castling_string.push('q');
+        }
+        if castling_string.is_empty() {
+            castling_string.push('-');
+        }
         format!(
-            "{} {} - {} 0 0",
+            "{} {} {} {} {} {}",
             board_string,
             match self.color_turn {
                 PieceColor::White => "w",
                 PieceColor::Black => "b",
             },
+            castling_string,
             if self.en_passant_colunm < 8 {
                 match self.color_turn {
                     PieceColor::White => BoardPosition::new(self.en_passant_colunm, 5).to_text(),
@@ -293,62 +467,222 @@ impl BoardState {
                 }
             } else {
                 "-".to_string()
-            }
+            },
+            self.halfmove_clock,
+            self.fullmove_number
         )
-        .to_string()
     }
-    pub fn from_fen(fen: &str) -> BoardState {
+    pub fn from_fen(fen: &str) -> Result<BoardState, FenParseError> {
         let mut space_splitter = fen.split_whitespace();
-        let pieces_str = space_splitter.next().unwrap();
-        let turn_str = space_splitter.next().unwrap();
-        let castling_str = space_splitter.next().unwrap();
-        let en_passant_str = space_splitter.next().unwrap();
-        // fullmove and halfmove number are ignored becuase they arent used
-        let mut ranks_str = pieces_str.split("/");
+        let pieces_str = space_splitter
+            .next()
+            .ok_or(FenParseError::MissingField("piece placement"))?;
+        let turn_str = space_splitter
+            .next()
+            .ok_or(FenParseError::MissingField("active color"))?;
+        let castling_str = space_splitter
+            .next()
+            .ok_or(FenParseError::MissingField("castling availability"))?;
+        let en_passant_str = space_splitter
+            .next()
+            .ok_or(FenParseError::MissingField("en passant target square"))?;
+        // the clocks are often left out (for example in EPD files) so they are optional
+        let halfmove_clock = match space_splitter.next() {
+            Some(halfmove_str) => halfmove_str
+                .parse::<u16>()
+                .map_err(|_| FenParseError::InvalidHalfmoveClock(halfmove_str.to_string()))?,
+            None => 0,
+        };
+        let fullmove_number = match space_splitter.next() {
+            Some(fullmove_str) => fullmove_str
+                .parse::<u16>()
+                .map_err(|_| FenParseError::InvalidFullmoveNumber(fullmove_str.to_string()))?,
+            None => 1,
+        };
+        if let Some(extra_str) = space_splitter.next() {
+            return Err(FenParseError::UnexpectedField(extra_str.to_string()));
+        }
+
+        let ranks_str: Vec<&str> = pieces_str.split('/').collect();
+        if ranks_str.len() != 8 {
+            return Err(FenParseError::InvalidRankCount(ranks_str.len()));
+        }
         let mut pieces = [[None::<Piece>; 8]; 8];
-        for y in (0..8).rev() {
-            let mut rank_chars = ranks_str.next().unwrap().chars();
+        for (rank_index, rank_str) in ranks_str.iter().enumerate() {
+            let y = 7 - rank_index;
             let mut x = 0;
-            loop {
-                let piece_char_option = rank_chars.next();
-                if let Some(piece_char) = piece_char_option {
-                    let piece_number_option = piece_char.to_string().parse::<u8>();
-                    if let Ok(n) = piece_number_option {
-                        x += n;
-                    } else {
-                        pieces[y as usize][x as usize] = Some(Piece::from_fen_char(piece_char));
-                        x += 1;
+            for piece_char in rank_str.chars() {
+                if let Some(n) = piece_char.to_digit(10) {
+                    if n == 0 || x + n as usize > 8 {
+                        return Err(FenParseError::InvalidRank(rank_str.to_string()));
                     }
+                    x += n as usize;
                 } else {
-                    break;
+                    let piece = Piece::try_from_fen_char(piece_char)
+                        .ok_or(FenParseError::InvalidPiece(piece_char))?;
+                    if x >= 8 {
+                        return Err(FenParseError::InvalidRank(rank_str.to_string()));
+                    }
+                    pieces[y][x] = Some(piece);
+                    x += 1;
                 }
             }
+            if x != 8 {
+                return Err(FenParseError::InvalidRank(rank_str.to_string()));
+            }
+        }
+        for color in [PieceColor::White, PieceColor::Black].iter() {
+            let king_count = pieces
+                .iter()
+                .flat_map(|rank| rank.iter())
+                .filter(|piece| **piece == Some(Piece::new(*color, PieceType::King)))
+                .count();
+            if king_count != 1 {
+                return Err(FenParseError::InvalidKingCount(*color, king_count));
+            }
         }
+
         let color_turn = match turn_str {
             "w" => PieceColor::White,
             "b" => PieceColor::Black,
-            _ => panic!("invalid fen color turn"),
+            _ => return Err(FenParseError::InvalidColorTurn(turn_str.to_string())),
         };
+        if castling_str != "-" && !castling_str.chars().all(|c| "KQkq".contains(c)) {
+            return Err(FenParseError::InvalidCastlingRights(
+                castling_str.to_string(),
+            ));
+        }
+        // a right is only kept while the king and that rook have not moved, so both
+        // must still be on their starting squares
+        for right in castling_str.chars().filter(|c| *c != '-') {
+            let (color, y) = if right.is_ascii_uppercase() {
+                (PieceColor::White, 0)
+            } else {
+                (PieceColor::Black, 7)
+            };
+            let rook_x = if right.eq_ignore_ascii_case(&'k') {
+                7
+            } else {
+                0
+            };
+            if pieces[y][4] != Some(Piece::new(color, PieceType::King))
+                || pieces[y][rook_x] != Some(Piece::new(color, PieceType::Rook))
+            {
+                return Err(FenParseError::CastlingPiecesMoved(right));
+            }
+        }
         let white_king_castle = castling_str.contains("K");
         let white_queen_castle = castling_str.contains("Q");
         let black_king_castle = castling_str.contains("k");
         let black_queen_castle = castling_str.contains("q");
         let en_passant_colunm = match en_passant_str {
             "-" => 55,
-            _ => BoardPosition::from_text(en_passant_str).x,
+            _ => {
+                // the target square is behind the pawn that just moved two squares
+                let expected_rank = match color_turn {
+                    PieceColor::White => 5,
+                    PieceColor::Black => 2,
+                };
+                match BoardPosition::try_from_text(en_passant_str) {
+                    Some(pos) if pos.y == expected_rank => pos.x,
+                    _ => return Err(FenParseError::InvalidEnPassant(en_passant_str.to_string())),
+                }
+            }
         };
-        BoardState {
-            pieces,
+        let mut board_state = BoardState {
+            pieces: [[None; 8]; 8],
+            piece_bitboards: [[0; 6]; 2],
+            color_bitboards: [0; 2],
             color_turn,
             white_king_castle,
             white_queen_castle,
             black_king_castle,
             black_queen_castle,
             en_passant_colunm,
+            halfmove_clock,
+            fullmove_number,
+            position_history: Vec::new(),
+            zobrist_key: 0,
+        };
+        for (y, rank) in pieces.iter().enumerate() {
+            for (x, piece) in rank.iter().enumerate() {
+                board_state.set(BoardPosition::new(x as u8, y as u8), *piece);
+            }
+        }
+        board_state.zobrist_key = board_state.calculate_zobrist_key();
+        Ok(board_state)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FenParseError {
+    MissingField(&'static str),
+    UnexpectedField(String),
+    InvalidRankCount(usize),
+    InvalidRank(String),
+    InvalidPiece(char),
+    InvalidKingCount(PieceColor, usize),
+    InvalidColorTurn(String),
+    InvalidCastlingRights(String),
+    CastlingPiecesMoved(char),
+    InvalidEnPassant(String),
+    InvalidHalfmoveClock(String),
+    InvalidFullmoveNumber(String),
+}
+
+impl fmt::Display for FenParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenParseError::MissingField(field) => write!(f, "fen is missing the {} field", field),
+            FenParseError::UnexpectedField(field) => {
+                write!(f, "unexpected field '{}' at the end of the fen", field)
+            }
+            FenParseError::InvalidRankCount(count) => {
+                write!(f, "expected 8 ranks in the fen but found {}", count)
+            }
+            FenParseError::InvalidRank(rank) => {
+                write!(f, "rank '{}' does not describe exactly 8 squares", rank)
+            }
+            FenParseError::InvalidPiece(c) => write!(f, "'{}' is not a valid piece", c),
+            FenParseError::InvalidKingCount(color, count) => write!(
+                f,
+                "{:?} must have exactly one king but has {}",
+                color, count
+            ),
+            FenParseError::InvalidColorTurn(turn) => {
+                write!(
+                    f,
+                    "'{}' is not a valid active color, expected 'w' or 'b'",
+                    turn
+                )
+            }
+            FenParseError::InvalidCastlingRights(castling) => {
+                write!(f, "'{}' is not valid castling availability", castling)
+            }
+            FenParseError::CastlingPiecesMoved(right) => write!(
+                f,
+                "castling right '{}' needs the king and rook on their starting squares",
+                right
+            ),
+            FenParseError::InvalidEnPassant(en_passant) => {
+                write!(
+                    f,
+                    "'{}' is not a valid en passant target square",
+                    en_passant
+                )
+            }
+            FenParseError::InvalidHalfmoveClock(halfmove) => {
+                write!(f, "'{}' is not a valid halfmove clock", halfmove)
+            }
+            FenParseError::InvalidFullmoveNumber(fullmove) => {
+                write!(f, "'{}' is not a valid fullmove number", fullmove)
+            }
         }
     }
 }
 
+impl std::error::Error for FenParseError {}
+
 impl fmt::Debug for BoardState {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut board_string = String::new();
@@ -372,20 +706,21 @@ impl fmt::Debug for BoardState {
 
         write!(
             f,
-            "pieces: \n{}\ncolor_turn: {:?}\nen_passant_colunm: {}\ncan_white_castle: {} {}\ncan_black_castle {} {} ",
+            "pieces: \n{}\ncolor_turn: {:?}\nen_passant_colunm: {}\ncan_white_castle: {} {}\ncan_black_castle {} {}\nhalfmove_clock: {} ",
             board_string,
             self.color_turn,
             self.en_passant_colunm,
             self.white_king_castle,
             self.white_queen_castle,
             self.black_king_castle,
-            self.black_queen_castle
+            self.black_queen_castle,
+            self.halfmove_clock
         )
     }
 }
 
 impl Default for BoardState {
     fn default() -> BoardState {
-        BoardState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
+        BoardState::from_fen(STARTING_FEN).unwrap()
     }
 }
@@ -0,0 +1,31 @@
+use super::BoardPosition;
+
+// a set of squares where bit y * 8 + x is set when the square at x, y is in the set
+pub type Bitboard = u64;
+
+pub fn position_bitboard(pos: BoardPosition) -> Bitboard {
+    1 << pos.to_index()
+}
+
+pub fn squares(bitboard: Bitboard) -> Squares {
+    Squares { bitboard }
+}
+
+// iterates over the positions in a bitboard from a1 to h8
+pub struct Squares {
+    bitboard: Bitboard,
+}
+
+impl Iterator for Squares {
+    type Item = BoardPosition;
+    fn next(&mut self) -> Option<BoardPosition> {
+        if self.bitboard == 0 {
+            None
+        } else {
+            let index = self.bitboard.trailing_zeros() as u8;
+            // clear the lowest set bit
+            self.bitboard &= self.bitboard - 1;
+            Some(BoardPosition::from_index(index))
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use super::{BoardPosition, BoardState, FenParseError, STARTING_FEN};
+use crate::{find_legal_actions, Action, ActionType, PieceColor};
+
+const FEN_CORPUS: [&str; 12] = [
+    STARTING_FEN,
+    "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+    "rnbqkbnr/ppp1pppp/8/8/3pP2P/8/PPPP1PP1/RNBQKBNR b KQkq e3 0 3",
+    "rnbqkbnr/1pppp1pp/p7/4Pp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "r3k2r/8/8/8/8/8/8/R3K2R b Kq - 12 40",
+    "4k3/8/8/8/8/8/4P3/4K3 w - - 99 80",
+    "8/8/8/8/8/8/8/K6k b - - 0 112",
+];
+
+#[test]
+fn fen_round_trip() {
+    for fen in FEN_CORPUS.iter() {
+        let board_state = BoardState::from_fen(fen).unwrap();
+        assert_eq!(board_state.to_fen(), *fen);
+        assert!(BoardState::from_fen(&board_state.to_fen()).unwrap() == board_state);
+    }
+}
+
+#[test]
+fn fen_round_trip_after_moves() {
+    // every position reachable in two moves from the corpus must also round trip
+    for fen in FEN_CORPUS.iter() {
+        let board_state = BoardState::from_fen(fen).unwrap();
+        for action in find_legal_actions(&board_state).0 {
+            let mut child = board_state.clone();
+            action.play_move(&mut child);
+            for child_action in find_legal_actions(&child).0 {
+                let mut grandchild = child.clone();
+                child_action.play_move(&mut grandchild);
+                let fen = grandchild.to_fen();
+                assert_eq!(BoardState::from_fen(&fen).unwrap().to_fen(), fen);
+            }
+        }
+    }
+}
+
+#[test]
+fn fen_clocks_and_rights_update() {
+    let mut board_state = BoardState::default();
+    let after_e4 = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+    let after_e5 = "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2";
+    let after_ke2 = "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPPKPPP/RNBQ1BNR b kq - 1 2";
+    for expected_fen in [after_e4, after_e5, after_ke2].iter() {
+        let expected = BoardState::from_fen(expected_fen).unwrap();
+        let action = find_legal_actions(&board_state)
+            .0
+            .into_iter()
+            .find(|action| {
+                let mut child = board_state.clone();
+                action.play_move(&mut child);
+                child == expected
+            })
+            .expect("no move leads to the expected position");
+        action.play_move(&mut board_state);
+        assert_eq!(board_state.to_fen(), *expected_fen);
+    }
+}
+
+#[test]
+fn fen_optional_clocks() {
+    let board_state =
+        BoardState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -").unwrap();
+    assert_eq!(board_state.to_fen(), STARTING_FEN);
+}
+
+#[test]
+fn fen_parse_errors() {
+    let assert_error = |fen: &str, expected: FenParseError| {
+        assert_eq!(BoardState::from_fen(fen).err(), Some(expected), "{}", fen);
+    };
+    assert_error("", FenParseError::MissingField("piece placement"));
+    assert_error(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w",
+        FenParseError::MissingField("castling availability"),
+    );
+    assert_error(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1",
+        FenParseError::InvalidRankCount(7),
+    );
+    assert_error(
+        "rnbqkbnr/ppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        FenParseError::InvalidRank("ppppppp".to_string()),
+    );
+    assert_error(
+        "rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        FenParseError::InvalidRank("9".to_string()),
+    );
+    assert_error(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNRR w KQkq - 0 1",
+        FenParseError::InvalidRank("RNBQKBNRR".to_string()),
+    );
+    assert_error(
+        "rnbqkbnr/pppppppp/8/8/3x4/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        FenParseError::InvalidPiece('x'),
+    );
+    assert_error(
+        "rnbqqbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        FenParseError::InvalidKingCount(PieceColor::Black, 0),
+    );
+    assert_error(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+        FenParseError::InvalidColorTurn("x".to_string()),
+    );
+    assert_error(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQxq - 0 1",
+        FenParseError::InvalidCastlingRights("KQxq".to_string()),
+    );
+    assert_error(
+        "4k3/8/8/8/8/8/8/4K3 w K - 0 1",
+        FenParseError::CastlingPiecesMoved('K'),
+    );
+    assert_error(
+        "4k3/8/8/8/8/8/8/3K3R w K - 0 1",
+        FenParseError::CastlingPiecesMoved('K'),
+    );
+    assert_error(
+        "1r2k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+        FenParseError::CastlingPiecesMoved('q'),
+    );
+    assert_error(
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e4 0 1",
+        FenParseError::InvalidEnPassant("e4".to_string()),
+    );
+    assert_error(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - -1 1",
+        FenParseError::InvalidHalfmoveClock("-1".to_string()),
+    );
+    assert_error(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 one",
+        FenParseError::InvalidFullmoveNumber("one".to_string()),
+    );
+    assert_error(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 moves",
+        FenParseError::UnexpectedField("moves".to_string()),
+    );
+}
+
+#[test]
+fn zobrist_key_updates_incrementally() {
+    for fen in FEN_CORPUS.iter() {
+        let board_state = BoardState::from_fen(fen).unwrap();
+        for action in find_legal_actions(&board_state).0 {
+            let mut child = board_state.clone();
+            action.play_move(&mut child);
+            for child_action in find_legal_actions(&child).0 {
+                let mut grandchild = child.clone();
+                child_action.play_move(&mut grandchild);
+                let recalculated = BoardState::from_fen(&grandchild.to_fen()).unwrap();
+                assert_eq!(
+                    grandchild.zobrist_key(),
+                    recalculated.zobrist_key(),
+                    "{}",
+                    grandchild.to_fen()
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn zobrist_key_transposition() {
+    let play = |moves: &[&str]| {
+        let mut board_state = BoardState::default();
+        for move_text in moves {
+            let (from_text, to_text) = move_text.split_at(2);
+            let from = BoardPosition::from_text(from_text);
+            let to = BoardPosition::from_text(to_text);
+            Action::new(ActionType::SimpleMove { from, to }).play_move(&mut board_state);
+        }
+        board_state
+    };
+    let first_order = play(&["g1f3", "g8f6", "b1c3"]);
+    let second_order = play(&["b1c3", "g8f6", "g1f3"]);
+    assert_eq!(first_order.zobrist_key(), second_order.zobrist_key());
+    let different = play(&["b1c3", "g8f6", "g1h3"]);
+    assert_ne!(first_order.zobrist_key(), different.zobrist_key());
+}
+
+#[test]
+fn null_move_passes_the_turn() {
+    let mut board_state =
+        BoardState::from_fen("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3")
+            .unwrap();
+    board_state.play_null_move();
+    assert_eq!(
+        board_state.to_fen(),
+        "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 3"
+    );
+    let recalculated = BoardState::from_fen(&board_state.to_fen()).unwrap();
+    assert_eq!(board_state.zobrist_key(), recalculated.zobrist_key());
+    assert_eq!(board_state.repetition_count(), 0);
+}
@@ -0,0 +1,86 @@
+use super::{BoardPosition, Piece, PieceColor, PieceType};
+
+pub struct ZobristKeys {
+    pieces: [u64; 12 * 64],
+    castling: [u64; 4],
+    en_passant: [u64; 8],
+    black_to_move: u64,
+}
+
+// the keys are generated at compile time so they are the same every time the engine runs
+static ZOBRIST_KEYS: ZobristKeys = ZobristKeys::generate(0x9E37_79B9_7F4A_7C15);
+
+impl ZobristKeys {
+    const fn generate(seed: u64) -> ZobristKeys {
+        let mut state = seed;
+        let mut pieces = [0; 12 * 64];
+        let mut i = 0;
+        while i < pieces.len() {
+            state = next_state(state);
+            pieces[i] = mix(state);
+            i += 1;
+        }
+        let mut castling = [0; 4];
+        let mut i = 0;
+        while i < castling.len() {
+            state = next_state(state);
+            castling[i] = mix(state);
+            i += 1;
+        }
+        let mut en_passant = [0; 8];
+        let mut i = 0;
+        while i < en_passant.len() {
+            state = next_state(state);
+            en_passant[i] = mix(state);
+            i += 1;
+        }
+        state = next_state(state);
+        ZobristKeys {
+            pieces,
+            castling,
+            en_passant,
+            black_to_move: mix(state),
+        }
+    }
+}
+
+const fn next_state(state: u64) -> u64 {
+    state.wrapping_add(0x9E37_79B9_7F4A_7C15)
+}
+
+const fn mix(state: u64) -> u64 {
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+pub fn piece_key(piece: Piece, pos: BoardPosition) -> u64 {
+    let color_index = match piece.color {
+        PieceColor::White => 0,
+        PieceColor::Black => 6,
+    };
+    let type_index = match piece.piece_type {
+        PieceType::Pawn => 0,
+        PieceType::Knight => 1,
+        PieceType::Bishop => 2,
+        PieceType::Rook => 3,
+        PieceType::Queen => 4,
+        PieceType::King => 5,
+    };
+    let square_index = pos.y as usize * 8 + pos.x as usize;
+    ZOBRIST_KEYS.pieces[(color_index + type_index) * 64 + square_index]
+}
+
+// castling rights are indexed in fen order: white kings side, white queens side, black kings side, black queens side
+pub fn castling_key(index: usize) -> u64 {
+    ZOBRIST_KEYS.castling[index]
+}
+
+pub fn en_passant_key(colunm: u8) -> u64 {
+    ZOBRIST_KEYS.en_passant[colunm as usize]
+}
+
+pub fn black_to_move_key() -> u64 {
+    ZOBRIST_KEYS.black_to_move
+}
@@ -0,0 +1,112 @@
+#[cfg(test)]
+mod book_builder_tests;
+
+use crate::best_action_finder::{OpeningBook, DEFAULT_BOOK_DEPTH};
+use crate::pgn::{parse_games, split_games, PgnGame};
+use crate::{GameEndState, PieceColor};
+use std::fs;
+use std::time::Instant;
+
+// moves played in fewer games than this are left out of the book by default
+pub const DEFAULT_MIN_GAMES: u32 = 3;
+
+// counts the moves from the first max_ply plies of every game, moves played in fewer
+// than min_games games are left out
+pub fn build_book(games: &[PgnGame], min_games: u32, max_ply: usize) -> OpeningBook {
+    let mut book = OpeningBook::new();
+    for game in games {
+        book.add_game(
+            &game.starting_position,
+            &game.actions,
+            game_result(game),
+            max_ply,
+        );
+    }
+    book.remove_rare_moves(min_games);
+    book
+}
+
+// reads every game in the pgn text that can be parsed, returning them with the number
+// of games that were skipped because they couldnt be
+pub fn read_games(text: &str) -> (Vec<PgnGame>, usize) {
+    let mut games = Vec::new();
+    let mut skipped = 0;
+    for game_text in split_games(text) {
+        match parse_games(game_text) {
+            Ok(parsed) => games.extend(parsed),
+            Err(_) => skipped += 1,
+        }
+    }
+    (games, skipped)
+}
+
+fn game_result(game: &PgnGame) -> Option<GameEndState> {
+    match game.header("Result") {
+        Some("1-0") => Some(GameEndState::Win(PieceColor::White)),
+        Some("0-1") => Some(GameEndState::Win(PieceColor::Black)),
+        Some("1/2-1/2") => Some(GameEndState::Draw),
+        _ => None,
+    }
+}
+
+// runs the build-book command line subcommand with the arguments after the subcommand
+// usage: build-book <output> <pgn file>... [--min-games n] [--max-ply n]
+pub fn run(args: &[String]) {
+    let usage = "usage: build-book <output> <pgn file>... [--min-games n] [--max-ply n]";
+    let mut min_games = DEFAULT_MIN_GAMES;
+    let mut max_ply = DEFAULT_BOOK_DEPTH as usize;
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--min-games" => match args.next().map(|value| value.parse()) {
+                Some(Ok(value)) => min_games = value,
+                _ => {
+                    println!("--min-games needs a number of games");
+                    return;
+                }
+            },
+            "--max-ply" => match args.next().map(|value| value.parse()) {
+                Some(Ok(value)) => max_ply = value,
+                _ => {
+                    println!("--max-ply needs a number of plies");
+                    return;
+                }
+            },
+            _ => paths.push(arg),
+        }
+    }
+    if paths.len() < 2 {
+        println!("{}", usage);
+        return;
+    }
+
+    let start_time = Instant::now();
+    let mut games = Vec::new();
+    let mut skipped = 0;
+    for path in paths[1..].iter() {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) => {
+                println!("could not read {}: {}", path, err);
+                return;
+            }
+        };
+        let (file_games, file_skipped) = read_games(&text);
+        games.extend(file_games);
+        skipped += file_skipped;
+    }
+    let book = build_book(&games, min_games, max_ply);
+    if let Err(err) = fs::write(paths[0], book.to_text()) {
+        println!("could not write {}: {}", paths[0], err);
+        return;
+    }
+    println!(
+        "wrote {} positions from {} games to {} in {} ms, skipped {} games that could not be parsed",
+        book.len(),
+        games.len(),
+        paths[0],
+        start_time.elapsed().as_millis(),
+        skipped
+    );
+}
@@ -0,0 +1,60 @@
+use super::{build_book, read_games};
+use crate::pgn::parse_games;
+use crate::{Action, BoardState};
+
+const GAMES: &str = r#"[Result "1-0"]
+1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 1-0
+
+[Result "0-1"]
+1. e4 c5 2. Nf3 d6 0-1
+
+[Result "1/2-1/2"]
+1. e4 e5 2. Nf3 Nf6 1/2-1/2
+
+[Result "*"]
+1. d4 d5 *
+"#;
+
+#[test]
+fn build_from_pgn() {
+    let games = parse_games(GAMES).unwrap();
+    let start = BoardState::default();
+    let e4 = Action::from_uci(&start, "e2e4").unwrap();
+
+    let book = build_book(&games, 1, 3);
+    let moves = book.moves(&start);
+    assert_eq!(moves.len(), 2);
+    assert_eq!(moves[0].action, e4);
+    assert_eq!(
+        (
+            moves[0].games,
+            moves[0].white_wins,
+            moves[0].draws,
+            moves[0].black_wins
+        ),
+        (3, 1, 1, 1)
+    );
+    assert_eq!((moves[1].games, moves[1].white_wins), (1, 0));
+    let mut after_nf3 = games[0].starting_position.clone();
+    for action in games[0].actions.iter().take(3) {
+        action.play_move(&mut after_nf3);
+    }
+    // only the first three plies of each game are in the book
+    assert!(book.moves(&after_nf3).is_empty());
+
+    let book = build_book(&games, 2, 100);
+    assert_eq!(book.moves(&start).len(), 1);
+    assert_eq!(book.moves(&after_nf3).len(), 0);
+    assert_eq!(book.len(), 3);
+}
+
+#[test]
+fn bad_games_are_skipped() {
+    let pgn = format!(
+        "{}\n[Result \"1-0\"]\n1. e4 e5 2. e5 1-0\n\n[FEN \"not a fen\"]\n*\n\n{}",
+        GAMES, GAMES
+    );
+    let (games, skipped) = read_games(&pgn);
+    assert_eq!(games.len(), 8);
+    assert_eq!(skipped, 2);
+}
@@ -0,0 +1,24 @@
+use crate::best_action_finder::EvalParams;
+use crate::{BoardState, EndgameTableSearcher, Evaluator, STARTING_FEN};
+
+// runs the eval command line subcommand with the arguments after the subcommand, it
+// prints what each term of the evaluation adds up to for the position
+// usage: eval [fen]
+pub fn run(args: &[String]) {
+    let fen = if args.is_empty() {
+        STARTING_FEN.to_string()
+    } else {
+        args.join(" ")
+    };
+    let board_state = match BoardState::from_fen(&fen) {
+        Ok(board_state) => board_state,
+        Err(err) => {
+            println!("invalid fen: {}", err);
+            println!("usage: eval [fen]");
+            return;
+        }
+    };
+    let mut evaluator = Evaluator::new(EndgameTableSearcher::new());
+    evaluator.set_params(EvalParams::from_resources());
+    println!("{}", evaluator.trace(&board_state));
+}
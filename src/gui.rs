@@ -1,6 +1,8 @@
-use crate::{
-    find_legal_actions, Action, ActionType, BoardPosition, BoardState, Piece, PieceColor,
-    PieceSetImages,
+use crate::player::{PlayerConfig, PlayerType};
+use crate::resource_loader::PieceSetImages;
+use chess_engine::{
+    find_legal_actions, Action, ActionType, BoardPosition, BoardState, GameEndState, Piece,
+    PieceColor,
 };
 use ggez::{
     graphics,
@@ -16,59 +18,30 @@ const BOARD_SIZE: f32 = 600.0;
 const BOARD_POS_SIZE: f32 = BOARD_SIZE / 8.0;
 const BOARD_MARGINS: f32 = 10.0;
 const HIGHLIGHT_COLOR: graphics::Color = graphics::Color::new(1.0, 1.0, 1.0, 0.3);
+const PROMOTION_BACKGROUND_COLOR: graphics::Color = graphics::Color::new(1.0, 1.0, 1.0, 0.9);
 const PROGRESS_BAR_HEIGHT: f32 = 20.0;
 const PROGRESS_BAR_Y_MARGINS: f32 = 0.0;
 const PROGRESS_BAR_X_MARGINS: f32 = 10.0;
+// the line the engine expects is written under the progress bar
+const EXPECTED_LINE_HEIGHT: f32 = 24.0;
+const EXPECTED_LINE_X_MARGINS: f32 = 10.0;
 
 const ARROW_WIDTH: f32 = 7.0;
 const ARROW_COLOR: graphics::Color =
     graphics::Color::new(240.0 / 255.0, 14.0 / 255.0, 52.0 / 255.0, 1.0);
 
 pub const WINDOW_WIDTH: f32 = BOARD_SIZE + 2.0 * BOARD_MARGINS;
-pub const WINDOW_HEIGHT: f32 =
-    BOARD_SIZE + BOARD_MARGINS + 2.0 * PROGRESS_BAR_Y_MARGINS + PROGRESS_BAR_HEIGHT;
+pub const WINDOW_HEIGHT: f32 = BOARD_SIZE
+    + BOARD_MARGINS
+    + 2.0 * PROGRESS_BAR_Y_MARGINS
+    + PROGRESS_BAR_HEIGHT
+    + EXPECTED_LINE_HEIGHT;
 
 enum Sellection {
     None,
     Selected(BoardPosition),
 }
 
-fn screen_pos_to_board_pos(x: f32, y: f32) -> Option<BoardPosition> {
-    let x = x - BOARD_MARGINS;
-    let y = y - BOARD_MARGINS;
-    if x > 0.0 && y > 0.0 {
-        let board_pos = BoardPosition::new(
-            (x / BOARD_POS_SIZE as f32) as u8,
-            (y / BOARD_POS_SIZE as f32) as u8,
-        );
-        if board_pos.bound_check() {
-            let inverted_board_pos = BoardPosition::new(board_pos.x, 7 - board_pos.y);
-            Some(inverted_board_pos)
-        } else {
-            None
-        }
-    } else {
-        None
-    }
-}
-
-fn board_pos_to_screen_pos(board_pos: BoardPosition) -> Point2<f32> {
-    Point2::new(
-        board_pos.x as f32 * BOARD_POS_SIZE + BOARD_MARGINS + (BOARD_POS_SIZE / 2.0),
-        (7 - board_pos.y) as f32 * BOARD_POS_SIZE + BOARD_MARGINS + (BOARD_POS_SIZE / 2.0),
-    )
-}
-
-fn board_pos_to_screen_rect(board_pos: BoardPosition) -> Rect {
-    let centered_pos = board_pos_to_screen_pos(board_pos);
-    Rect::new(
-        centered_pos.x - (BOARD_POS_SIZE / 2.0),
-        centered_pos.y - (BOARD_POS_SIZE / 2.0),
-        BOARD_POS_SIZE,
-        BOARD_POS_SIZE,
-    )
-}
-
 pub struct GUIState {
     sellection: Sellection,
     white_piece_images: PieceSetImages,
@@ -78,12 +51,19 @@ pub struct GUIState {
     pending_move: Option<Action>,
     progress_bar_percentage: f32,
     last_played_move: Option<PlayerAction>,
+    // the score and principal variation from the engine's last search
+    expected_line: String,
+    // the squares the promotion pieces are shown on when the player must pick one
+    promotion_choices: Vec<(BoardPosition, Action)>,
+    // when the board is flipped black is shown at the bottom of the window
+    flipped: bool,
 }
 impl GUIState {
     pub fn new(
         white_piece_images: PieceSetImages,
         black_piece_images: PieceSetImages,
         board_image: graphics::Image,
+        flipped: bool,
     ) -> GUIState {
         GUIState {
             sellection: Sellection::None,
@@ -94,16 +74,59 @@ impl GUIState {
             pending_move: None,
             progress_bar_percentage: 1.0,
             last_played_move: None,
+            expected_line: String::new(),
+            promotion_choices: Vec::with_capacity(4),
+            flipped,
         }
     }
 
-    pub fn update_last_played_move(&mut self, action: Option<Action>, color: PieceColor) {
-        self.last_played_move = match action {
-            Some(action) => Some(PlayerAction::new(action, color)),
-            None => None,
+    fn screen_pos_to_board_pos(&self, x: f32, y: f32) -> Option<BoardPosition> {
+        let x = x - BOARD_MARGINS;
+        let y = y - BOARD_MARGINS;
+        if x > 0.0 && y > 0.0 {
+            let board_pos =
+                BoardPosition::new((x / BOARD_POS_SIZE) as u8, (y / BOARD_POS_SIZE) as u8);
+            if board_pos.bound_check() {
+                let inverted_board_pos = if self.flipped {
+                    BoardPosition::new(7 - board_pos.x, board_pos.y)
+                } else {
+                    BoardPosition::new(board_pos.x, 7 - board_pos.y)
+                };
+                Some(inverted_board_pos)
+            } else {
+                None
+            }
+        } else {
+            None
         }
     }
 
+    fn board_pos_to_screen_pos(&self, board_pos: BoardPosition) -> Point2<f32> {
+        let (column, row) = if self.flipped {
+            (7 - board_pos.x, board_pos.y)
+        } else {
+            (board_pos.x, 7 - board_pos.y)
+        };
+        Point2::new(
+            column as f32 * BOARD_POS_SIZE + BOARD_MARGINS + (BOARD_POS_SIZE / 2.0),
+            row as f32 * BOARD_POS_SIZE + BOARD_MARGINS + (BOARD_POS_SIZE / 2.0),
+        )
+    }
+
+    fn board_pos_to_screen_rect(&self, board_pos: BoardPosition) -> Rect {
+        let centered_pos = self.board_pos_to_screen_pos(board_pos);
+        Rect::new(
+            centered_pos.x - (BOARD_POS_SIZE / 2.0),
+            centered_pos.y - (BOARD_POS_SIZE / 2.0),
+            BOARD_POS_SIZE,
+            BOARD_POS_SIZE,
+        )
+    }
+
+    pub fn update_last_played_move(&mut self, last_action: Option<(Action, PieceColor)>) {
+        self.last_played_move = last_action.map(|(action, color)| PlayerAction::new(action, color));
+    }
+
     pub fn check_for_action(&mut self) -> Option<Action> {
         match self.pending_move {
             Some(pending_move) => {
@@ -126,7 +149,9 @@ impl GUIState {
         self.draw_highlighted_squares(ctx);
         self.draw_pieces(ctx, board_state);
         self.draw_progress_bar(ctx);
+        self.draw_expected_line(ctx);
         self.draw_last_played_action(ctx);
+        self.draw_promotion_choices(ctx, board_state);
     }
     fn draw_board(&self, ctx: &mut Context) {
         graphics::draw(
@@ -143,7 +168,7 @@ impl GUIState {
     }
     fn draw_piece(&self, ctx: &mut Context, board_pos: BoardPosition, piece: &Piece) {
         let piece_image = self.get_piece_image(*piece);
-        let centered_pos = board_pos_to_screen_pos(board_pos);
+        let centered_pos = self.board_pos_to_screen_pos(board_pos);
         graphics::draw(
             ctx,
             piece_image,
@@ -174,7 +199,7 @@ impl GUIState {
         let mut drawn_a_square = false;
         let mut highlight_mesh = graphics::MeshBuilder::new();
         let mut highlight_square = |pos: BoardPosition| {
-            let rect = board_pos_to_screen_rect(pos);
+            let rect = self.board_pos_to_screen_rect(pos);
             highlight_mesh.rectangle(graphics::DrawMode::fill(), rect, HIGHLIGHT_COLOR);
             drawn_a_square = true;
         };
@@ -190,10 +215,35 @@ impl GUIState {
             graphics::draw(ctx, &mesh, graphics::DrawParam::new()).unwrap();
         }
     }
+    fn draw_promotion_choices(&self, ctx: &mut Context, board_state: &BoardState) {
+        if self.promotion_choices.is_empty() {
+            return;
+        }
+        let mut background_mesh = graphics::MeshBuilder::new();
+        for (pos, _) in self.promotion_choices.iter() {
+            background_mesh.rectangle(
+                graphics::DrawMode::fill(),
+                self.board_pos_to_screen_rect(*pos),
+                PROMOTION_BACKGROUND_COLOR,
+            );
+        }
+        let mesh = background_mesh.build(ctx).unwrap();
+        graphics::draw(ctx, &mesh, graphics::DrawParam::new()).unwrap();
+        for (pos, action) in self.promotion_choices.iter() {
+            if let ActionType::Promotion {
+                from: _,
+                to: _,
+                piece_type,
+            } = action.get_action_type()
+            {
+                self.draw_piece(ctx, *pos, &Piece::new(board_state.color_turn, piece_type));
+            }
+        }
+    }
     fn draw_last_played_action(&self, ctx: &mut Context) {
         if let Some(action) = &self.last_played_move {
-            let screen_space_from = board_pos_to_screen_pos(action.from);
-            let screen_space_to = board_pos_to_screen_pos(action.to);
+            let screen_space_from = self.board_pos_to_screen_pos(action.from);
+            let screen_space_to = self.board_pos_to_screen_pos(action.to);
             self.draw_arrow(ctx, screen_space_from, screen_space_to);
         }
     }
@@ -222,7 +272,19 @@ impl GUIState {
         graphics::draw(ctx, &arrow_head_mesh, graphics::DrawParam::new()).unwrap();
     }
     pub fn click(&mut self, x: f32, y: f32, board_state: &BoardState) {
-        let board_pos = screen_pos_to_board_pos(x, y);
+        let board_pos = self.screen_pos_to_board_pos(x, y);
+        if !self.promotion_choices.is_empty() {
+            // clicking anywhere other than one of the choices cancels the promotion
+            if let Some(board_pos) = board_pos {
+                for (pos, action) in self.promotion_choices.iter() {
+                    if *pos == board_pos {
+                        self.pending_move = Some(*action);
+                    }
+                }
+            }
+            self.promotion_choices.clear();
+            return;
+        }
         if let Some(board_pos) = board_pos {
             if let Sellection::Selected(selection) = self.sellection {
                 if selection == board_pos {
@@ -232,7 +294,7 @@ impl GUIState {
                 }
             } else {
                 if let Some(piece) = board_state.get(board_pos) {
-                    if piece.color == PieceColor::White {
+                    if piece.color == board_state.color_turn {
                         self.select(board_pos, board_state);
                     }
                 }
@@ -244,7 +306,7 @@ impl GUIState {
     fn select(&mut self, pos: BoardPosition, board_state: &BoardState) {
         self.sellection = Sellection::Selected(pos);
 
-        let all_possible_actions = find_legal_actions(board_state, false).0;
+        let all_possible_actions = find_legal_actions(board_state).0;
 
         for action in all_possible_actions {
             let player_action = PlayerAction::new(action, board_state.color_turn);
@@ -254,13 +316,33 @@ impl GUIState {
         }
     }
     fn try_move_to(&mut self, pos: BoardPosition) -> bool {
-        for possible_move in self.possible_moves_from_selection.iter() {
-            if possible_move.to == pos {
-                self.pending_move = Some(possible_move.this_action);
-                return true;
+        let moves_to_pos: Vec<Action> = self
+            .possible_moves_from_selection
+            .iter()
+            .filter(|possible_move| possible_move.to == pos)
+            .map(|possible_move| possible_move.this_action)
+            .collect();
+        if moves_to_pos.len() == 1 {
+            self.pending_move = Some(moves_to_pos[0]);
+            true
+        } else if moves_to_pos.len() > 1 {
+            // there is more than one move to the same square when promoting
+            // so the choices are shown in a column going back down the board
+            for (i, action) in moves_to_pos.into_iter().enumerate() {
+                let choice_y = if pos.y == 7 { 7 - i as u8 } else { i as u8 };
+                self.promotion_choices
+                    .push((BoardPosition::new(pos.x, choice_y), action));
             }
+            true
+        } else {
+            false
         }
-        false
+    }
+    // forgets any half made move, used when the position being shown changes
+    pub fn reset_selection(&mut self) {
+        self.deselect();
+        self.promotion_choices.clear();
+        self.pending_move = None;
     }
     fn deselect(&mut self) {
         self.sellection = Sellection::None;
@@ -290,6 +372,29 @@ impl GUIState {
         .unwrap();
         graphics::draw(ctx, &rect_mesh, graphics::DrawParam::new()).unwrap();
     }
+    pub fn update_expected_line(&mut self, expected_line: String) {
+        self.expected_line = expected_line;
+    }
+    fn draw_expected_line(&self, ctx: &mut Context) {
+        if self.expected_line.is_empty() {
+            return;
+        }
+        let text = graphics::Text::new(self.expected_line.as_str());
+        let text_height = text.height(ctx) as f32;
+        let y = BOARD_MARGINS
+            + BOARD_SIZE
+            + 2.0 * PROGRESS_BAR_Y_MARGINS
+            + PROGRESS_BAR_HEIGHT
+            + (EXPECTED_LINE_HEIGHT - text_height) / 2.0;
+        graphics::draw(
+            ctx,
+            &text,
+            graphics::DrawParam::new()
+                .dest(Point2::new(EXPECTED_LINE_X_MARGINS, y))
+                .color(graphics::BLACK),
+        )
+        .unwrap();
+    }
 }
 
 struct PlayerAction {
@@ -315,6 +420,11 @@ impl PlayerAction {
                 PieceColor::Black => BoardPosition::new(4, 7),
             },
             ActionType::EnPassant { from, to: _ } => from,
+            ActionType::Promotion {
+                from,
+                to: _,
+                piece_type: _,
+            } => from,
         }
     }
     fn find_to(action: Action, color: PieceColor) -> BoardPosition {
@@ -332,19 +442,31 @@ impl PlayerAction {
                 }
             }
             ActionType::EnPassant { from: _, to } => to,
+            ActionType::Promotion {
+                from: _,
+                to,
+                piece_type: _,
+            } => to,
         }
     }
 }
 
-pub fn show_player_wins_message() {
-    msgbox::create("You Win", "Congratulations - You Win!", IconType::Info)
-        .expect("Error creating message box");
-}
-pub fn show_computer_wins_message() {
-    msgbox::create("Computer Won", "The computer won.", IconType::Info)
-        .expect("Error creating message box");
-}
-pub fn show_draw_message() {
-    msgbox::create("Draw", "You drew with the computer.", IconType::Info)
-        .expect("Error creating message box");
+pub fn show_game_end_message(game_end: GameEndState, players: &PlayerConfig) {
+    // a human playing against the engine is told whether they won, otherwise the
+    // result is given by color
+    let human_color = match (players.white, players.black) {
+        (PlayerType::Human, PlayerType::Engine) => Some(PieceColor::White),
+        (PlayerType::Engine, PlayerType::Human) => Some(PieceColor::Black),
+        _ => None,
+    };
+    let (title, message) = match (game_end, human_color) {
+        (GameEndState::Draw, Some(_)) => ("Draw", "You drew with the computer.".to_string()),
+        (GameEndState::Draw, None) => ("Draw", "The game was drawn.".to_string()),
+        (GameEndState::Win(winner), Some(human_color)) if winner == human_color => {
+            ("You Win", "Congratulations - You Win!".to_string())
+        }
+        (GameEndState::Win(_), Some(_)) => ("Computer Won", "The computer won.".to_string()),
+        (GameEndState::Win(winner), None) => ("Checkmate", format!("{:?} won.", winner)),
+    };
+    msgbox::create(title, &message, IconType::Info).expect("Error creating message box");
 }
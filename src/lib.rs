@@ -0,0 +1,24 @@
+// the chess engine without the gui, the board representation, move generation,
+// evaluation and search can all be used from other crates through the items here
+mod actions;
+pub mod best_action_finder;
+mod board_state;
+pub mod book_builder;
+pub mod eval;
+pub mod perft;
+pub mod pgn;
+pub mod tuner;
+pub mod uci;
+
+pub use actions::{
+    find_legal_actions, in_check, side_to_move_in_check, Action, ActionType, GameEndState,
+    NotationError,
+};
+pub use best_action_finder::{
+    BestActionFinder, Clock, EndgameTableSearcher, EvaluationTrace, Evaluator, Minimax, Score,
+    SearchLimits, SearchResult, TranspositionTable,
+};
+pub use board_state::{
+    BoardPosition, BoardState, Capturable, FenParseError, Piece, PieceColor, PieceType,
+    STARTING_FEN,
+};
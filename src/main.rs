@@ -1,23 +1,76 @@
-mod actions;
-mod best_action_finder;
-mod board_state;
 mod gui;
+mod move_history;
+mod player;
 mod resource_loader;
 
-pub use actions::{find_legal_actions, in_check, Action, ActionType, GameEndState};
-pub use board_state::{BoardPosition, BoardState, Capturable, Piece, PieceColor, PieceType};
-
-use best_action_finder::BestActionFinder;
+use chess_engine::best_action_finder::{self, mate_distance, BestActionFinder, SearchResult};
+use chess_engine::pgn::PgnGame;
+use chess_engine::{
+    book_builder, eval, find_legal_actions, perft, tuner, uci, Action, BoardState, PieceColor,
+};
 use ggez::{
-    event::{self, EventHandler, MouseButton},
+    event::{self, EventHandler, KeyCode, KeyMods, MouseButton},
     graphics,
     input::mouse::set_cursor_type,
     Context, ContextBuilder, GameResult,
 };
 use gui::{GUIState, WINDOW_HEIGHT, WINDOW_WIDTH};
-use resource_loader::{get_resource_path, PieceSetImages};
+use move_history::MoveHistory;
+use player::{PlayerConfig, PlayerType};
+use resource_loader::get_resource_path;
+use std::path::PathBuf;
+use std::{env, fs};
+
+// where games are saved to and loaded from when no --pgn option is given
+const DEFAULT_PGN_PATH: &str = "game.pgn";
+const EXPECTED_LINE_MAX_MOVES: usize = 12;
 
 fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.iter().any(|arg| arg == "--uci") {
+        uci::run();
+        return;
+    }
+    match args.get(1).map(|arg| arg.as_str()) {
+        Some(command @ "perft") | Some(command @ "divide") => {
+            perft::run(command, &args[2..]);
+            return;
+        }
+        Some("build-book") => {
+            book_builder::run(&args[2..]);
+            return;
+        }
+        Some("eval") => {
+            eval::run(&args[2..]);
+            return;
+        }
+        Some("tune") => {
+            tuner::run(&args[2..]);
+            return;
+        }
+        _ => (),
+    }
+    let players = match PlayerConfig::from_args(&args[1..]) {
+        Ok(players) => players,
+        Err(err) => {
+            println!("{}", err);
+            println!(
+                "usage: chess_engine [--white human|engine] [--black human|engine] [--pgn file]"
+            );
+            return;
+        }
+    };
+    let pgn_path = match args.iter().position(|arg| arg == "--pgn") {
+        Some(i) => match args.get(i + 1) {
+            Some(path) => PathBuf::from(path),
+            None => {
+                println!("--pgn needs a file to save and load games with");
+                return;
+            }
+        },
+        None => PathBuf::from(DEFAULT_PGN_PATH),
+    };
+
     let (mut ctx, mut event_loop) = ContextBuilder::new("chess engine", "Christopher Lang")
         .window_setup(ggez::conf::WindowSetup::default().title("Chess Engine"))
         .window_mode(ggez::conf::WindowMode::default().dimensions(WINDOW_WIDTH, WINDOW_HEIGHT))
@@ -25,7 +78,7 @@ fn main() {
         .build()
         .expect("error creating ggez context");
 
-    let mut game = ChessGame::new(&mut ctx);
+    let mut game = ChessGame::new(&mut ctx, players, pgn_path);
 
     println!("Chess Engine Project | Created by Christopher Lang for his computer science EPQ");
     println!("End game tables by Massimiliano Goi - https://chess.massimilianogoi.com/download/tablebases/");
@@ -36,79 +89,180 @@ fn main() {
     }
 }
 
+// the score from white's side in pawns, or who is mating, and the line the engine
+// expects from the position it searched
+fn describe_search(result: &SearchResult, board_state: &BoardState) -> String {
+    let score_text = match mate_distance(result.score) {
+        Some((PieceColor::White, _)) => "white mates".to_string(),
+        Some((PieceColor::Black, _)) => "black mates".to_string(),
+        None => format!("{:+.2}", -(result.score as f32) / 100.0),
+    };
+    let pv = result.pv_to_san(board_state);
+    // only as much of the line as fits under the board
+    let moves: Vec<&str> = pv.split(' ').take(EXPECTED_LINE_MAX_MOVES).collect();
+    format!("{} depth {}: {}", score_text, result.depth, moves.join(" "))
+}
+
 struct ChessGame {
-    board_state: BoardState,
+    move_history: MoveHistory,
     gui_state: GUIState,
-    chess_computer: BestActionFinder,
+    players: PlayerConfig,
+    // each side played by the engine has its own action finder, indexed by color
+    chess_computers: [Option<BestActionFinder>; 2],
     game_over: bool,
+    pgn_path: PathBuf,
 }
 
 impl ChessGame {
-    pub fn new(ctx: &mut Context) -> ChessGame {
+    pub fn new(ctx: &mut Context, players: PlayerConfig, pgn_path: PathBuf) -> ChessGame {
+        let chess_computer = |player_type| match player_type {
+            PlayerType::Human => None,
+            PlayerType::Engine => Some(BestActionFinder::new()),
+        };
         let new_game = ChessGame {
-            board_state: BoardState::default(),
+            move_history: MoveHistory::new(BoardState::default()),
             gui_state: GUIState::new(
                 resource_loader::load_white_piece_set(ctx),
                 resource_loader::load_black_piece_set(ctx),
                 resource_loader::load_board_image(ctx),
+                players.flip_board(),
             ),
-            chess_computer: BestActionFinder::new(),
+            players,
+            chess_computers: [chess_computer(players.white), chess_computer(players.black)],
             game_over: false,
+            pgn_path,
         };
         new_game
     }
 
+    fn board_state(&self) -> &BoardState {
+        self.move_history.current_position()
+    }
+
+    fn is_human_turn(&self) -> bool {
+        self.players.player_type(self.board_state().color_turn) == PlayerType::Human
+    }
+
     fn play_move(&mut self, action: Action, ctx: &mut Context) {
-        self.gui_state
-            .update_last_played_move(Some(action), self.board_state.color_turn);
         println!("");
-        println!("{:?} moved", self.board_state.color_turn);
-        action.play_move(&mut self.board_state);
-        println!("{:?}", self.board_state);
-        if let Some(game_end) = find_legal_actions(&self.board_state, false).1 {
+        println!(
+            "{:?} played {}",
+            self.board_state().color_turn,
+            action.to_san(self.board_state())
+        );
+        self.move_history.play(action);
+        println!("{:?}", self.board_state());
+        self.show_current_position(ctx);
+        if let Some(game_end) = find_legal_actions(self.board_state()).1 {
             self.draw(ctx).unwrap();
-            match game_end {
-                GameEndState::Draw => {
-                    gui::show_draw_message();
-                }
-                GameEndState::Win(PieceColor::White) => {
-                    gui::show_player_wins_message();
-                }
-                GameEndState::Win(PieceColor::Black) => {
-                    gui::show_computer_wins_message();
+            gui::show_game_end_message(game_end, &self.players);
+        }
+    }
+
+    // updates everything shown to match the position the move history is on, the
+    // engines only search when the last position in the history is being shown
+    fn show_current_position(&mut self, ctx: &mut Context) {
+        for chess_computer in self.chess_computers.iter_mut().flatten() {
+            chess_computer.cancel();
+        }
+        self.gui_state.reset_selection();
+        self.gui_state.update_progress_bar(1.0);
+        // the line from the last search no longer starts from the position shown
+        self.gui_state.update_expected_line(String::new());
+        self.gui_state
+            .update_last_played_move(self.move_history.last_action());
+        self.game_over = find_legal_actions(self.board_state()).1.is_some();
+        set_cursor_type(ctx, ggez::input::mouse::MouseCursor::Default);
+    }
+
+    // goes back to the last position where a human was to move, so against the
+    // engine both its reply and the players move are taken back
+    fn undo(&mut self, ctx: &mut Context) {
+        let has_human = self.players.has_human();
+        while self.move_history.step_back() && has_human && !self.is_human_turn() {}
+        self.show_current_position(ctx);
+    }
+
+    fn save_game(&self) {
+        let mut game = PgnGame::new(
+            self.move_history.starting_position().clone(),
+            self.move_history.actions().to_vec(),
+        );
+        game.set_header("White", self.players.white.name());
+        game.set_header("Black", self.players.black.name());
+        match fs::write(&self.pgn_path, game.to_pgn()) {
+            Ok(()) => println!("Saved the game to {}", self.pgn_path.display()),
+            Err(err) => println!(
+                "Couldnt save the game to {}: {}",
+                self.pgn_path.display(),
+                err
+            ),
+        }
+    }
+
+    // replaces the game with the first game in the pgn file, showing its last position
+    fn load_game(&mut self, ctx: &mut Context) {
+        let game = fs::read_to_string(&self.pgn_path)
+            .map_err(|err| err.to_string())
+            .and_then(|pgn| PgnGame::parse(&pgn).map_err(|err| err.to_string()));
+        match game {
+            Ok(game) => {
+                self.move_history = MoveHistory::new(game.starting_position);
+                for action in game.actions {
+                    self.move_history.play(action);
                 }
+                self.show_current_position(ctx);
+                println!("Loaded the game from {}", self.pgn_path.display());
+                println!("{:?}", self.board_state());
             }
-            self.game_over = true
+            Err(err) => println!(
+                "Couldnt load the game from {}: {}",
+                self.pgn_path.display(),
+                err
+            ),
         }
     }
+
+    fn redo(&mut self, ctx: &mut Context) {
+        let has_human = self.players.has_human();
+        while self.move_history.step_forward() && has_human && !self.is_human_turn() {}
+        self.show_current_position(ctx);
+    }
 }
 
 impl EventHandler for ChessGame {
     fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
-        if !self.game_over {
-            match self.board_state.color_turn {
-                PieceColor::White => {
-                    if let Some(action) = self.gui_state.check_for_action() {
-                        self.play_move(action, ctx);
-                    }
+        if self.game_over {
+            return Ok(());
+        }
+        let color_turn = self.board_state().color_turn;
+        match &mut self.chess_computers[color_turn as usize] {
+            None => {
+                if let Some(action) = self.gui_state.check_for_action() {
+                    self.play_move(action, ctx);
+                }
+            }
+            Some(chess_computer) => {
+                // looking back through the game pauses the engines
+                if !self.move_history.is_at_end() {
+                    return Ok(());
                 }
-                PieceColor::Black => {
-                    let chess_computer_state = self.chess_computer.get_state();
-                    match chess_computer_state {
-                        best_action_finder::State::Idle => {
-                            set_cursor_type(ctx, ggez::input::mouse::MouseCursor::Wait);
-                            self.chess_computer.start_finding_move(&self.board_state);
-                        }
-                        best_action_finder::State::Thinking(progress) => {
-                            self.gui_state.update_progress_bar(progress)
-                        }
-                        best_action_finder::State::Finished(Err(err)) => {
-                            panic!("error finding move: {}", err)
-                        }
-                        best_action_finder::State::Finished(Ok(action)) => {
-                            set_cursor_type(ctx, ggez::input::mouse::MouseCursor::Default);
-                            self.play_move(action, ctx)
-                        }
+                let chess_computer_state = chess_computer.get_state();
+                match chess_computer_state {
+                    best_action_finder::State::Idle => {
+                        set_cursor_type(ctx, ggez::input::mouse::MouseCursor::Wait);
+                        chess_computer.start_finding_move(self.move_history.current_position());
+                    }
+                    best_action_finder::State::Thinking(progress) => {
+                        self.gui_state.update_progress_bar(progress)
+                    }
+                    best_action_finder::State::Finished(Err(err)) => {
+                        panic!("error finding move: {}", err)
+                    }
+                    best_action_finder::State::Finished(Ok(result)) => {
+                        let expected_line = describe_search(&result, self.board_state());
+                        self.play_move(result.action, ctx);
+                        self.gui_state.update_expected_line(expected_line);
                     }
                 }
             }
@@ -118,16 +272,42 @@ impl EventHandler for ChessGame {
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
         graphics::clear(ctx, graphics::WHITE);
-        self.gui_state.draw(ctx, &self.board_state);
+        self.gui_state
+            .draw(ctx, self.move_history.current_position());
         graphics::present(ctx)
     }
 
     fn mouse_button_down_event(&mut self, _ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
-        if button == MouseButton::Left
-            && self.board_state.color_turn == PieceColor::White
-            && !self.game_over
-        {
-            self.gui_state.click(x, y, &self.board_state);
+        if button == MouseButton::Left && self.is_human_turn() && !self.game_over {
+            self.gui_state
+                .click(x, y, self.move_history.current_position());
+        }
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        keymods: KeyMods,
+        _repeat: bool,
+    ) {
+        match keycode {
+            KeyCode::Escape => event::quit(ctx),
+            KeyCode::Z if keymods.contains(KeyMods::CTRL) => self.undo(ctx),
+            KeyCode::Y if keymods.contains(KeyMods::CTRL) => self.redo(ctx),
+            KeyCode::S if keymods.contains(KeyMods::CTRL) => self.save_game(),
+            KeyCode::O if keymods.contains(KeyMods::CTRL) => self.load_game(ctx),
+            KeyCode::Left if self.move_history.step_back() => self.show_current_position(ctx),
+            KeyCode::Right if self.move_history.step_forward() => self.show_current_position(ctx),
+            KeyCode::Home => {
+                self.move_history.go_to_start();
+                self.show_current_position(ctx);
+            }
+            KeyCode::End => {
+                self.move_history.go_to_end();
+                self.show_current_position(ctx);
+            }
+            _ => (),
         }
     }
 }
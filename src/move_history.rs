@@ -0,0 +1,84 @@
+use chess_engine::{Action, BoardState, PieceColor};
+
+// every position reached in the game along with the moves between them, one of the
+// positions is the one being shown, moves after it are kept until a different move is
+// played so they can be redone
+pub struct MoveHistory {
+    positions: Vec<BoardState>,
+    actions: Vec<Action>,
+    current: usize,
+}
+
+impl MoveHistory {
+    pub fn new(board_state: BoardState) -> MoveHistory {
+        MoveHistory {
+            positions: vec![board_state],
+            actions: Vec::new(),
+            current: 0,
+        }
+    }
+
+    pub fn current_position(&self) -> &BoardState {
+        &self.positions[self.current]
+    }
+
+    pub fn starting_position(&self) -> &BoardState {
+        &self.positions[0]
+    }
+
+    // every move in the history, including any after the current position
+    pub fn actions(&self) -> &[Action] {
+        &self.actions
+    }
+
+    // the move that led to the current position and the color that played it
+    pub fn last_action(&self) -> Option<(Action, PieceColor)> {
+        if self.current == 0 {
+            None
+        } else {
+            let color = self.positions[self.current - 1].color_turn;
+            Some((self.actions[self.current - 1], color))
+        }
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        self.current == self.actions.len()
+    }
+
+    // plays the action from the current position, replacing any moves that came after it
+    pub fn play(&mut self, action: Action) {
+        let mut board_state = self.current_position().clone();
+        action.play_move(&mut board_state);
+        self.positions.truncate(self.current + 1);
+        self.actions.truncate(self.current);
+        self.positions.push(board_state);
+        self.actions.push(action);
+        self.current += 1;
+    }
+
+    pub fn step_back(&mut self) -> bool {
+        if self.current > 0 {
+            self.current -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn step_forward(&mut self) -> bool {
+        if self.current < self.actions.len() {
+            self.current += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn go_to_start(&mut self) {
+        self.current = 0;
+    }
+
+    pub fn go_to_end(&mut self) {
+        self.current = self.actions.len();
+    }
+}
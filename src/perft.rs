@@ -0,0 +1,83 @@
+#[cfg(test)]
+mod perft_tests;
+
+use crate::{find_legal_actions, Action, BoardState, STARTING_FEN};
+use std::time::Instant;
+
+// counts the leaf nodes of the move tree to the given depth, the counts for well
+// known positions are published so they can be used to check the move generation
+pub fn perft(board_state: &BoardState, depth: u8) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let legal_actions = find_legal_actions(board_state).0;
+    // the leaves dont need to be played to be counted
+    if depth == 1 {
+        return legal_actions.len() as u64;
+    }
+    legal_actions
+        .iter()
+        .map(|action| {
+            let mut new_board_state = board_state.clone();
+            action.play_move(&mut new_board_state);
+            perft(&new_board_state, depth - 1)
+        })
+        .sum()
+}
+
+// the perft count below each legal action, used to find which move a wrong count comes from
+pub fn divide(board_state: &BoardState, depth: u8) -> Vec<(Action, u64)> {
+    assert!(depth != 0, "depth for divide cant be 0");
+    find_legal_actions(board_state)
+        .0
+        .into_iter()
+        .map(|action| {
+            let mut new_board_state = board_state.clone();
+            action.play_move(&mut new_board_state);
+            (action, perft(&new_board_state, depth - 1))
+        })
+        .collect()
+}
+
+// runs the perft or divide command line subcommand with the arguments after the subcommand
+// usage: perft <depth> [fen] or divide <depth> [fen]
+pub fn run(command: &str, args: &[String]) {
+    let depth = match args.first().map(|depth| depth.parse::<u8>()) {
+        Some(Ok(depth)) if depth > 0 => depth,
+        _ => {
+            println!("usage: {} <depth> [fen]", command);
+            return;
+        }
+    };
+    let fen = if args.len() > 1 {
+        args[1..].join(" ")
+    } else {
+        STARTING_FEN.to_string()
+    };
+    let board_state = match BoardState::from_fen(&fen) {
+        Ok(board_state) => board_state,
+        Err(err) => {
+            println!("invalid fen: {}", err);
+            return;
+        }
+    };
+    let start_time = Instant::now();
+    let nodes = if command == "divide" {
+        let mut nodes = 0;
+        for (action, action_nodes) in divide(&board_state, depth) {
+            println!("{}: {}", action.to_uci(&board_state), action_nodes);
+            nodes += action_nodes;
+        }
+        println!();
+        nodes
+    } else {
+        perft(&board_state, depth)
+    };
+    let elapsed = start_time.elapsed();
+    println!("nodes: {}", nodes);
+    println!(
+        "time: {} ms ({} nodes per second)",
+        elapsed.as_millis(),
+        (nodes as f64 / elapsed.as_secs_f64().max(0.001)) as u64
+    );
+}
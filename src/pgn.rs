@@ -0,0 +1,385 @@
+#[cfg(test)]
+mod pgn_tests;
+
+use crate::{
+    find_legal_actions, Action, BoardState, FenParseError, GameEndState, NotationError, PieceColor,
+    STARTING_FEN,
+};
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+// the movetext is wrapped so no line is longer than this
+const LINE_LENGTH: usize = 80;
+// the tags every game should have, in the order they are written, with the values
+// used when they are unknown
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+// a game record, the headers are kept in the order they were read or set
+#[derive(Clone, Debug)]
+pub struct PgnGame {
+    pub headers: Vec<(String, String)>,
+    pub starting_position: BoardState,
+    pub actions: Vec<Action>,
+}
+
+impl PgnGame {
+    // the result header is filled in from the final position, the other tags of the
+    // seven tag roster are left unknown
+    pub fn new(starting_position: BoardState, actions: Vec<Action>) -> PgnGame {
+        let mut game = PgnGame {
+            headers: SEVEN_TAG_ROSTER
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            starting_position,
+            actions,
+        };
+        let fen = game.starting_position.to_fen();
+        if fen != STARTING_FEN {
+            game.set_header("SetUp", "1");
+            game.set_header("FEN", &fen);
+        }
+        let result = result_text(find_legal_actions(&game.final_position()).1);
+        game.set_header("Result", result);
+        game
+    }
+
+    // reads the first game in the text
+    pub fn parse(text: &str) -> Result<PgnGame, PgnParseError> {
+        parse_games(text)?
+            .into_iter()
+            .next()
+            .ok_or(PgnParseError::NoGame)
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header_name, _)| header_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        match self
+            .headers
+            .iter_mut()
+            .find(|(header_name, _)| header_name == name)
+        {
+            Some((_, header_value)) => *header_value = value.to_string(),
+            None => self.headers.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn final_position(&self) -> BoardState {
+        let mut board_state = self.starting_position.clone();
+        for action in self.actions.iter() {
+            action.play_move(&mut board_state);
+        }
+        board_state
+    }
+
+    pub fn to_pgn(&self) -> String {
+        let mut pgn = String::new();
+        for (name, value) in self.headers.iter() {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, value));
+        }
+        pgn.push('\n');
+
+        let mut tokens = Vec::with_capacity(self.actions.len() + 1);
+        let mut board_state = self.starting_position.clone();
+        for (i, action) in self.actions.iter().enumerate() {
+            let san = action.to_san(&board_state);
+            // move numbers are kept on the same line as the move that follows them
+            if board_state.color_turn == PieceColor::White {
+                tokens.push(format!("{}. {}", board_state.fullmove_number, san));
+            } else if i == 0 {
+                tokens.push(format!("{}... {}", board_state.fullmove_number, san));
+            } else {
+                tokens.push(san);
+            }
+            action.play_move(&mut board_state);
+        }
+        tokens.push(self.header("Result").unwrap_or("*").to_string());
+
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length > 0 && line_length + 1 + token.len() > LINE_LENGTH {
+                pgn.push('\n');
+                line_length = 0;
+            } else if line_length > 0 {
+                pgn.push(' ');
+                line_length += 1;
+            }
+            pgn.push_str(&token);
+            line_length += token.len();
+        }
+        pgn.push('\n');
+        pgn
+    }
+}
+
+// reads every game in the text, each move is checked against the legal moves
+pub fn parse_games(text: &str) -> Result<Vec<PgnGame>, PgnParseError> {
+    let mut tokenizer = Tokenizer {
+        chars: text.chars().peekable(),
+    };
+    let mut games = Vec::new();
+    let mut headers = Vec::new();
+    let mut moves = Vec::new();
+    while let Some(token) = tokenizer.next_token()? {
+        match token {
+            Token::Tag(name, value) => {
+                // a tag after the movetext starts the next game when the last one had no result
+                if !moves.is_empty() {
+                    games.push(build_game(headers, moves)?);
+                    headers = Vec::new();
+                    moves = Vec::new();
+                }
+                headers.push((name, value));
+            }
+            Token::Move(san) => moves.push(san),
+            Token::Result(result) => {
+                if !headers.iter().any(|(name, _)| name == "Result") {
+                    headers.push(("Result".to_string(), result));
+                }
+                games.push(build_game(headers, moves)?);
+                headers = Vec::new();
+                moves = Vec::new();
+            }
+        }
+    }
+    if !headers.is_empty() || !moves.is_empty() {
+        games.push(build_game(headers, moves)?);
+    }
+    Ok(games)
+}
+
+// splits the text of a pgn file into the text of each game so a game that cant be
+// parsed doesnt stop the others from being read, a game starts at the first tag after
+// the movetext of the one before it
+pub fn split_games(text: &str) -> Vec<&str> {
+    let mut games = Vec::new();
+    let mut game_start = 0;
+    let mut line_start = 0;
+    let mut in_movetext = false;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('[') {
+            if in_movetext {
+                games.push(&text[game_start..line_start]);
+                game_start = line_start;
+                in_movetext = false;
+            }
+        } else if !trimmed.is_empty() {
+            in_movetext = true;
+        }
+        line_start += line.len();
+    }
+    games.push(&text[game_start..]);
+    games.retain(|game| !game.trim().is_empty());
+    games
+}
+
+fn build_game(
+    headers: Vec<(String, String)>,
+    moves: Vec<String>,
+) -> Result<PgnGame, PgnParseError> {
+    let starting_position = match headers.iter().find(|(name, _)| name == "FEN") {
+        Some((_, fen)) => BoardState::from_fen(fen).map_err(PgnParseError::InvalidFen)?,
+        None => BoardState::default(),
+    };
+    let mut board_state = starting_position.clone();
+    let mut actions = Vec::with_capacity(moves.len());
+    for san in moves {
+        let move_number = board_state.fullmove_number;
+        let action = match Action::from_san(&board_state, &san) {
+            Ok(action) => action,
+            Err(NotationError::AmbiguousMove(_)) => {
+                return Err(PgnParseError::AmbiguousMove { move_number, san })
+            }
+            Err(_) => return Err(PgnParseError::IllegalMove { move_number, san }),
+        };
+        action.play_move(&mut board_state);
+        actions.push(action);
+    }
+    Ok(PgnGame {
+        headers,
+        starting_position,
+        actions,
+    })
+}
+
+fn result_text(game_end: Option<GameEndState>) -> &'static str {
+    match game_end {
+        Some(GameEndState::Win(PieceColor::White)) => "1-0",
+        Some(GameEndState::Win(PieceColor::Black)) => "0-1",
+        Some(GameEndState::Draw) => "1/2-1/2",
+        None => "*",
+    }
+}
+
+// removes a move number like "12." or "12..." from the start of a symbol, digits that are
+// not followed by a dot are part of the move, like castling written as 0-0
+fn strip_move_number(symbol: &str) -> &str {
+    let after_digits = symbol.trim_start_matches(|c: char| c.is_ascii_digit());
+    if after_digits.len() < symbol.len() && after_digits.starts_with('.') {
+        after_digits.trim_start_matches('.')
+    } else {
+        symbol
+    }
+}
+
+enum Token {
+    Tag(String, String),
+    Move(String),
+    Result(String),
+}
+
+struct Tokenizer<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn next_char_if<F: Fn(char) -> bool>(&mut self, predicate: F) -> Option<char> {
+        match self.chars.peek() {
+            Some(&c) if predicate(c) => self.chars.next(),
+            _ => None,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.next_char_if(char::is_whitespace).is_some() {}
+    }
+
+    // comments, variations, move numbers and annotation glyphs are skipped over
+    fn next_token(&mut self) -> Result<Option<Token>, PgnParseError> {
+        while let Some(c) = self.chars.next() {
+            match c {
+                '[' => return self.read_tag().map(Some),
+                '{' => self.skip_comment()?,
+                ';' | '%' => while !matches!(self.chars.next(), Some('\n') | None) {},
+                '(' => self.skip_variation()?,
+                '$' => while self.next_char_if(|c| c.is_ascii_digit()).is_some() {},
+                c if c.is_whitespace() => (),
+                c => {
+                    let mut symbol = c.to_string();
+                    while let Some(c) =
+                        self.next_char_if(|c| !c.is_whitespace() && !"[]{}();$".contains(c))
+                    {
+                        symbol.push(c);
+                    }
+                    match symbol.as_str() {
+                        "1-0" | "0-1" | "1/2-1/2" | "*" => return Ok(Some(Token::Result(symbol))),
+                        // written after en passant captures
+                        "e.p." => (),
+                        _ => {
+                            // move numbers can be written with or without a space before the move
+                            let san = strip_move_number(&symbol);
+                            if !san.is_empty() {
+                                return Ok(Some(Token::Move(san.to_string())));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn read_tag(&mut self) -> Result<Token, PgnParseError> {
+        let mut name = String::new();
+        let mut value = String::new();
+        self.skip_whitespace();
+        while let Some(c) = self.next_char_if(|c| !c.is_whitespace() && c != '"') {
+            name.push(c);
+        }
+        self.skip_whitespace();
+        if self.chars.next() != Some('"') || name.is_empty() {
+            return Err(PgnParseError::InvalidTag(name));
+        }
+        loop {
+            match self.chars.next() {
+                Some('"') => break,
+                Some('\\') => match self.chars.next() {
+                    Some(c) => value.push(c),
+                    None => return Err(PgnParseError::InvalidTag(name)),
+                },
+                Some(c) => value.push(c),
+                None => return Err(PgnParseError::InvalidTag(name)),
+            }
+        }
+        self.skip_whitespace();
+        if self.chars.next() != Some(']') {
+            return Err(PgnParseError::InvalidTag(name));
+        }
+        Ok(Token::Tag(name, value))
+    }
+
+    fn skip_comment(&mut self) -> Result<(), PgnParseError> {
+        loop {
+            match self.chars.next() {
+                Some('}') => return Ok(()),
+                Some(_) => (),
+                None => return Err(PgnParseError::UnterminatedComment),
+            }
+        }
+    }
+
+    // variations can be nested and contain comments with brackets in them
+    fn skip_variation(&mut self) -> Result<(), PgnParseError> {
+        let mut depth = 1;
+        while depth > 0 {
+            match self.chars.next() {
+                Some('(') => depth += 1,
+                Some(')') => depth -= 1,
+                Some('{') => self.skip_comment()?,
+                Some(_) => (),
+                None => return Err(PgnParseError::UnterminatedVariation),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PgnParseError {
+    InvalidTag(String),
+    UnterminatedComment,
+    UnterminatedVariation,
+    InvalidFen(FenParseError),
+    IllegalMove { move_number: u16, san: String },
+    AmbiguousMove { move_number: u16, san: String },
+    NoGame,
+}
+
+impl fmt::Display for PgnParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnParseError::InvalidTag(name) => write!(f, "tag '{}' is not valid", name),
+            PgnParseError::UnterminatedComment => write!(f, "a comment is missing its '}}'"),
+            PgnParseError::UnterminatedVariation => write!(f, "a variation is missing its ')'"),
+            PgnParseError::InvalidFen(err) => write!(f, "invalid FEN tag: {}", err),
+            PgnParseError::IllegalMove { move_number, san } => {
+                write!(f, "'{}' at move {} is not a legal move", san, move_number)
+            }
+            PgnParseError::AmbiguousMove { move_number, san } => {
+                write!(
+                    f,
+                    "'{}' at move {} could be more than one move",
+                    san, move_number
+                )
+            }
+            PgnParseError::NoGame => write!(f, "no game was found"),
+        }
+    }
+}
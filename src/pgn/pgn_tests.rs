@@ -0,0 +1,137 @@
+use super::{parse_games, split_games, PgnGame, PgnParseError};
+use crate::BoardState;
+
+const OPERA_GAME: &str = r#"[Event "Paris"]
+[Site "Paris FRA"]
+[Date "1858.??.??"]
+[Round "?"]
+[White "Paul Morphy"]
+[Black "Duke Karl / Count Isouard"]
+[Result "1-0"]
+
+1. e4 e5 2. Nf3 d6 3. d4 Bg4 4. dxe5 Bxf3 5. Qxf3 dxe5 6. Bc4 Nf6 7. Qb3 Qe7
+8. Nc3 c6 9. Bg5 b5 10. Nxb5 cxb5 11. Bxb5+ Nbd7 12. O-O-O Rd8 13. Rxd7 Rxd7
+14. Rd1 Qe6 15. Bxd7+ Nxd7 16. Qb8+ Nxb8 17. Rd8# 1-0
+"#;
+
+#[test]
+fn parse_opera_game() {
+    let game = PgnGame::parse(OPERA_GAME).unwrap();
+    assert_eq!(game.actions.len(), 33);
+    assert_eq!(game.header("White"), Some("Paul Morphy"));
+    assert_eq!(game.header("Result"), Some("1-0"));
+    assert_eq!(
+        game.final_position().to_fen(),
+        "1n1Rkb1r/p4ppp/4q3/4p1B1/4P3/8/PPP2PPP/2K5 b k - 1 17"
+    );
+}
+
+#[test]
+fn write_opera_game() {
+    let game = PgnGame::parse(OPERA_GAME).unwrap();
+    assert_eq!(game.to_pgn(), OPERA_GAME);
+}
+
+#[test]
+fn new_game_result_and_headers() {
+    let game = PgnGame::parse(OPERA_GAME).unwrap();
+    let new_game = PgnGame::new(game.starting_position.clone(), game.actions.clone());
+    assert_eq!(new_game.header("Result"), Some("1-0"));
+    assert_eq!(new_game.header("Event"), Some("?"));
+    assert_eq!(new_game.header("FEN"), None);
+
+    let fen = "4k3/8/8/8/8/8/4p3/4K3 b - - 3 40";
+    let new_game = PgnGame::new(BoardState::from_fen(fen).unwrap(), Vec::new());
+    assert_eq!(new_game.header("Result"), Some("*"));
+    assert_eq!(new_game.header("SetUp"), Some("1"));
+    assert_eq!(new_game.header("FEN"), Some(fen));
+}
+
+#[test]
+fn round_trip_from_fen() {
+    let pgn =
+        "[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/7p/R3K3 b Q - 0 40\"]\n\n40... Kd7 41. O-O-O+ Kc6 *\n";
+    let game = PgnGame::parse(pgn).unwrap();
+    assert_eq!(game.actions.len(), 3);
+    assert_eq!(game.to_pgn(), pgn.replace("\n\n", "\n[Result \"*\"]\n\n"));
+}
+
+#[test]
+fn skips_comments_variations_and_glyphs() {
+    let pgn = "1. e4 {best by test} e5 (1... c5 {the sicilian} 2. Nf3 (2. c3)) 2. Nf3 $1 ; note\nNc6 3.Bb5 a6 *";
+    let game = PgnGame::parse(pgn).unwrap();
+    assert_eq!(game.actions.len(), 6);
+    assert_eq!(game.header("Result"), Some("*"));
+}
+
+#[test]
+fn castling_written_with_zeros() {
+    let games =
+        parse_games("1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4. 0-0 d6 5. d4 Bg4 6. Qd3 Qd7 7. Nc3 0-0-0 *")
+            .unwrap();
+    let san: Vec<String> = games[0]
+        .to_pgn()
+        .split_whitespace()
+        .map(String::from)
+        .collect();
+    assert!(san.contains(&"O-O".to_string()));
+    assert!(san.contains(&"O-O-O".to_string()));
+    assert_eq!(games[0].actions.len(), 14);
+}
+
+#[test]
+fn parses_several_games() {
+    let pgn = format!("{}\n{}\n1. d4 d5 1/2-1/2\n", OPERA_GAME, OPERA_GAME);
+    let games = parse_games(&pgn).unwrap();
+    assert_eq!(games.len(), 3);
+    assert_eq!(games[2].actions.len(), 2);
+    assert_eq!(games[2].header("Result"), Some("1/2-1/2"));
+}
+
+#[test]
+fn splits_games_at_their_tags() {
+    let pgn = format!("\n{}\n{}\n1. d4 d5 1/2-1/2\n", OPERA_GAME, OPERA_GAME);
+    let games = split_games(&pgn);
+    // the game without tags stays with the movetext before it
+    assert_eq!(games.len(), 2);
+    assert!(games[0].trim_start().starts_with("[Event"));
+    assert_eq!(parse_games(games[0]).unwrap().len(), 1);
+    assert!(games[1].ends_with("1. d4 d5 1/2-1/2\n"));
+    assert_eq!(parse_games(games[1]).unwrap().len(), 2);
+    assert!(split_games(" \n").is_empty());
+}
+
+#[test]
+fn parse_errors() {
+    assert_eq!(
+        PgnGame::parse("1. e4 e5 2. e5 *").unwrap_err(),
+        PgnParseError::IllegalMove {
+            move_number: 2,
+            san: "e5".to_string()
+        }
+    );
+    assert_eq!(
+        PgnGame::parse("[FEN \"4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1\"]\n1. Nd2 *").unwrap_err(),
+        PgnParseError::AmbiguousMove {
+            move_number: 1,
+            san: "Nd2".to_string()
+        }
+    );
+    assert!(matches!(
+        PgnGame::parse("[FEN \"not a fen\"]\n*"),
+        Err(PgnParseError::InvalidFen(_))
+    ));
+    assert_eq!(
+        PgnGame::parse("1. e4 {unfinished").unwrap_err(),
+        PgnParseError::UnterminatedComment
+    );
+    assert_eq!(
+        PgnGame::parse("1. e4 (1. d4").unwrap_err(),
+        PgnParseError::UnterminatedVariation
+    );
+    assert_eq!(
+        PgnGame::parse("[Event \"unterminated]").unwrap_err(),
+        PgnParseError::InvalidTag("Event".to_string())
+    );
+    assert_eq!(PgnGame::parse("").unwrap_err(), PgnParseError::NoGame);
+}
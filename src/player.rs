@@ -0,0 +1,84 @@
+use chess_engine::PieceColor;
+use std::str::FromStr;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PlayerType {
+    Human,
+    Engine,
+}
+
+impl PlayerType {
+    // the name written in the headers of saved games
+    pub fn name(&self) -> &'static str {
+        match self {
+            PlayerType::Human => "Human",
+            PlayerType::Engine => "Chess Engine",
+        }
+    }
+}
+
+impl FromStr for PlayerType {
+    type Err = String;
+    fn from_str(text: &str) -> Result<PlayerType, String> {
+        match text {
+            "human" => Ok(PlayerType::Human),
+            "engine" => Ok(PlayerType::Engine),
+            _ => Err(format!(
+                "unknown player '{}', expected human or engine",
+                text
+            )),
+        }
+    }
+}
+
+// who plays each side of the game
+#[derive(Copy, Clone, Debug)]
+pub struct PlayerConfig {
+    pub white: PlayerType,
+    pub black: PlayerType,
+}
+
+impl PlayerConfig {
+    // reads the --white and --black options, each side not given keeps its default
+    pub fn from_args(args: &[String]) -> Result<PlayerConfig, String> {
+        let mut config = PlayerConfig::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let player = match arg.as_str() {
+                "--white" => &mut config.white,
+                "--black" => &mut config.black,
+                _ => continue,
+            };
+            *player = match args.next() {
+                Some(player_type) => player_type.parse()?,
+                None => return Err(format!("{} needs a player, human or engine", arg)),
+            };
+        }
+        Ok(config)
+    }
+
+    pub fn player_type(&self, color: PieceColor) -> PlayerType {
+        match color {
+            PieceColor::White => self.white,
+            PieceColor::Black => self.black,
+        }
+    }
+
+    pub fn has_human(&self) -> bool {
+        self.white == PlayerType::Human || self.black == PlayerType::Human
+    }
+
+    // the board is shown from blacks side when black is the only human player
+    pub fn flip_board(&self) -> bool {
+        self.white == PlayerType::Engine && self.black == PlayerType::Human
+    }
+}
+
+impl Default for PlayerConfig {
+    fn default() -> PlayerConfig {
+        PlayerConfig {
+            white: PlayerType::Human,
+            black: PlayerType::Engine,
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::PieceType;
+use chess_engine::PieceType;
 use ggez::{graphics::Image, Context};
 use std::{env, path::PathBuf};
 
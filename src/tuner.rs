@@ -0,0 +1,253 @@
+#[cfg(test)]
+mod tuner_tests;
+
+use crate::best_action_finder::EvalParams;
+use crate::{BoardState, EndgameTableSearcher, Evaluator, FenParseError};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Instant;
+
+// passes over every parameter before giving up, a pass that improves nothing stops
+// the tuning early
+pub const DEFAULT_MAX_ITERATIONS: usize = 100;
+
+// a position and how the game it came from ended, 1 for a white win, 0.5 for a draw
+// and 0 for a black win
+#[derive(Clone, Debug, PartialEq)]
+pub struct LabelledPosition {
+    pub board_state: BoardState,
+    pub result: f64,
+}
+
+// one position per line, a fen followed by the result as 1-0, 1/2-1/2, 0-1 or 1.0, 0.5,
+// 0.0, quotes, brackets and semicolons around the result are ignored so the usual epd
+// files like `<fen> c9 "1/2-1/2";` can be read, lines starting with # are skipped
+pub fn parse_positions(text: &str) -> Result<Vec<LabelledPosition>, TunerError> {
+    let mut positions = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 5 {
+            return Err(TunerError::InvalidLine(line_number));
+        }
+        let result_field =
+            fields[fields.len() - 1].trim_matches(|c| c == '"' || c == ';' || c == '[' || c == ']');
+        let result = match result_field {
+            "1-0" | "1" | "1.0" => 1.0,
+            "1/2-1/2" | "0.5" => 0.5,
+            "0-1" | "0" | "0.0" => 0.0,
+            _ => return Err(TunerError::InvalidResult(line_number)),
+        };
+        // the clocks are used when they are there, anything else between the fen and the
+        // result is an epd operation
+        let has_clocks = fields.len() >= 7
+            && fields[4].parse::<u16>().is_ok()
+            && fields[5].parse::<u16>().is_ok();
+        let fen_fields = if has_clocks { 6 } else { 4 };
+        let board_state = BoardState::from_fen(&fields[..fen_fields].join(" "))
+            .map_err(|err| TunerError::InvalidFen(line_number, err))?;
+        positions.push(LabelledPosition {
+            board_state,
+            result,
+        });
+    }
+    Ok(positions)
+}
+
+pub fn load_positions(path: &Path) -> Result<Vec<LabelledPosition>, TunerError> {
+    parse_positions(&fs::read_to_string(path).map_err(TunerError::Io)?)
+}
+
+// the expected result of the game from a score in centipawns from white's side, k
+// scales the scores to match how often the positions were won
+pub fn sigmoid(score: f64, k: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * score / 400.0))
+}
+
+// how far the evaluation's predicted results are from the real ones on average
+pub fn mean_squared_error(evaluator: &Evaluator, positions: &[LabelledPosition], k: f64) -> f64 {
+    let total: f64 = positions
+        .iter()
+        .map(|position| {
+            // evaluations are from black's side
+            let score = -(evaluator.evaluate_terms(&position.board_state) as f64);
+            (position.result - sigmoid(score, k)).powi(2)
+        })
+        .sum();
+    total / positions.len().max(1) as f64
+}
+
+// the scaling constant that fits the current evaluation best, it is found once and
+// kept fixed so the tuner only changes the parameters
+pub fn find_k(evaluator: &Evaluator, positions: &[LabelledPosition]) -> f64 {
+    let mut best_k = 1.0;
+    let mut best_error = mean_squared_error(evaluator, positions, best_k);
+    let mut step = 0.5;
+    // a coarse search first and then finer ones around the best value so far
+    for _ in 0..4 {
+        let centre = best_k;
+        for i in -10..=10 {
+            let k = centre + i as f64 * step;
+            if k <= 0.0 {
+                continue;
+            }
+            let error = mean_squared_error(evaluator, positions, k);
+            if error < best_error {
+                best_error = error;
+                best_k = k;
+            }
+        }
+        step /= 10.0;
+    }
+    best_k
+}
+
+// texel tuning, each parameter in turn is moved up or down by one and the change is
+// kept when it lowers the error, until a whole pass makes no improvement or max_iterations
+// passes are done, on_iteration is called with the error and parameters after each pass
+pub fn tune(
+    evaluator: &mut Evaluator,
+    positions: &[LabelledPosition],
+    k: f64,
+    max_iterations: usize,
+    mut on_iteration: impl FnMut(usize, f64, &EvalParams),
+) -> EvalParams {
+    let mut params = evaluator.params().clone();
+    let mut values = params.values();
+    let mut best_error = mean_squared_error(evaluator, positions, k);
+    for iteration in 0..max_iterations {
+        let mut improved = false;
+        for i in 0..values.len() {
+            for step in [1, -1].iter() {
+                values[i] += step;
+                params.set_values(&values);
+                evaluator.set_params(params.clone());
+                let error = mean_squared_error(evaluator, positions, k);
+                if error < best_error {
+                    best_error = error;
+                    improved = true;
+                    break;
+                }
+                values[i] -= step;
+            }
+        }
+        params.set_values(&values);
+        evaluator.set_params(params.clone());
+        on_iteration(iteration + 1, best_error, &params);
+        if !improved {
+            break;
+        }
+    }
+    params
+}
+
+// runs the tune command line subcommand with the arguments after the subcommand
+// usage: tune <positions file> <output> [--params file] [--iterations n]
+pub fn run(args: &[String]) {
+    let usage = "usage: tune <positions file> <output> [--params file] [--iterations n]";
+    let mut max_iterations = DEFAULT_MAX_ITERATIONS;
+    let mut params = EvalParams::default();
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--params" => match args.next() {
+                Some(path) => match EvalParams::load(Path::new(path)) {
+                    Ok(loaded) => params = loaded,
+                    Err(err) => {
+                        println!("could not load {}: {}", path, err);
+                        return;
+                    }
+                },
+                None => {
+                    println!("--params needs a parameter file");
+                    return;
+                }
+            },
+            "--iterations" => match args.next().map(|value| value.parse()) {
+                Some(Ok(value)) => max_iterations = value,
+                _ => {
+                    println!("--iterations needs a number of passes");
+                    return;
+                }
+            },
+            _ => paths.push(arg),
+        }
+    }
+    if paths.len() != 2 {
+        println!("{}", usage);
+        return;
+    }
+
+    let positions = match load_positions(Path::new(paths[0])) {
+        Ok(positions) => positions,
+        Err(err) => {
+            println!("could not read {}: {}", paths[0], err);
+            return;
+        }
+    };
+    if positions.is_empty() {
+        println!("{} has no positions", paths[0]);
+        return;
+    }
+    let start_time = Instant::now();
+    let mut evaluator = Evaluator::new(EndgameTableSearcher::new());
+    evaluator.set_params(params);
+    let k = find_k(&evaluator, &positions);
+    println!(
+        "{} positions, k = {:.3}, error = {:.6}",
+        positions.len(),
+        k,
+        mean_squared_error(&evaluator, &positions, k)
+    );
+    let output = Path::new(paths[1]);
+    let on_iteration = |iteration, error, params: &EvalParams| {
+        println!(
+            "iteration {}: error = {:.6} ({} s)",
+            iteration,
+            error,
+            start_time.elapsed().as_secs()
+        );
+        // saved after every pass so a long run can be stopped at any time
+        if let Err(err) = params.save(output) {
+            println!("could not write {}: {}", output.display(), err);
+        }
+    };
+    let tuned = tune(&mut evaluator, &positions, k, max_iterations, on_iteration);
+    if let Err(err) = tuned.save(output) {
+        println!("could not write {}: {}", output.display(), err);
+        return;
+    }
+    println!("wrote the tuned parameters to {}", output.display());
+}
+
+#[derive(Debug)]
+pub enum TunerError {
+    Io(io::Error),
+    InvalidLine(usize),
+    InvalidFen(usize, FenParseError),
+    InvalidResult(usize),
+}
+
+impl fmt::Display for TunerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TunerError::Io(err) => write!(f, "{}", err),
+            TunerError::InvalidLine(line) => {
+                write!(f, "line {} should have a fen and a result", line)
+            }
+            TunerError::InvalidFen(line, err) => write!(f, "line {}: {}", line, err),
+            TunerError::InvalidResult(line) => write!(
+                f,
+                "line {} should end with a result of 1-0, 1/2-1/2 or 0-1",
+                line
+            ),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use super::{find_k, mean_squared_error, parse_positions, sigmoid, tune, TunerError};
+use crate::{EndgameTableSearcher, Evaluator};
+
+const POSITIONS: &str = "\
+# white is a knight up and won
+4k3/pppp1ppp/8/8/8/2N5/PPPP1PPP/4K3 w - - 0 1 1-0
+4k3/pppp1ppp/8/8/8/2N5/PPPP1PPP/4K3 b - - 0 1 [1.0]
+# black is a knight up and won
+4k3/pppp1ppp/2n5/8/8/8/PPPP1PPP/4K3 w - - 0 1 0-1
+4k3/pppp1ppp/2n5/8/8/8/PPPP1PPP/4K3 b - - c9 \"0-1\";
+# level and drawn
+4k3/pppp1ppp/8/8/8/8/PPPP1PPP/4K3 w - - 0 1 1/2-1/2
+4k3/pppp1ppp/8/8/8/8/PPPP1PPP/4K3 b - - 0.5
+";
+
+#[test]
+fn parse_positions_file() {
+    let positions = parse_positions(POSITIONS).unwrap();
+    let results: Vec<f64> = positions.iter().map(|position| position.result).collect();
+    assert_eq!(results, vec![1.0, 1.0, 0.0, 0.0, 0.5, 0.5]);
+    assert_eq!(
+        positions[0].board_state.to_fen(),
+        positions[1].board_state.to_fen().replace(" b ", " w ")
+    );
+}
+
+#[test]
+fn parse_errors() {
+    assert!(matches!(
+        parse_positions("4k3/8/8/8/8/8/8/4K3 w - - 0 1 2-0\n"),
+        Err(TunerError::InvalidResult(1))
+    ));
+    assert!(matches!(
+        parse_positions("\n4k3/8/8/8/8/8/8/4K3 w\n"),
+        Err(TunerError::InvalidLine(2))
+    ));
+    assert!(matches!(
+        parse_positions("4k3/8/8/8/8/8/4K3 w - - 0 1 1-0\n"),
+        Err(TunerError::InvalidFen(1, _))
+    ));
+}
+
+#[test]
+fn sigmoid_is_centred_on_a_draw() {
+    assert_eq!(sigmoid(0.0, 1.0), 0.5);
+    assert!(sigmoid(400.0, 1.0) > 0.9);
+    assert!((sigmoid(-250.0, 1.3) + sigmoid(250.0, 1.3) - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn tuning_lowers_the_error() {
+    let positions = parse_positions(POSITIONS).unwrap();
+    let mut evaluator = Evaluator::new(EndgameTableSearcher::new());
+    let k = find_k(&evaluator, &positions);
+    assert!(k > 0.0);
+    let before = mean_squared_error(&evaluator, &positions, k);
+    let mut errors = Vec::new();
+    let tuned = tune(&mut evaluator, &positions, k, 2, |_, error, _| {
+        errors.push(error)
+    });
+    let after = mean_squared_error(&evaluator, &positions, k);
+    assert_eq!(evaluator.params(), &tuned);
+    assert_eq!(errors.last(), Some(&after));
+    assert!(after < before);
+    // the extra knight decides every one of these games so it gets more valuable
+    assert!(tuned.knight_value > 320);
+}
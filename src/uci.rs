@@ -0,0 +1,402 @@
+use crate::best_action_finder::{
+    mate_distance, BestActionFinder, Clock, PruningOptions, Score, SearchLimits, SearchResult,
+    State, TermWeights, DEFAULT_BOOK_DEPTH, DEFAULT_DEPTH, DEFAULT_TRANSPOSITION_TABLE_SIZE_MB,
+    MAX_DEPTH,
+};
+use crate::{Action, BoardState, PieceColor};
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+const ENGINE_NAME: &str = "Chess Engine";
+const ENGINE_AUTHOR: &str = "Christopher Lang";
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+const MAX_HASH_SIZE_MB: usize = 4096;
+const MAX_BOOK_DEPTH: u16 = 1000;
+// the evaluation terms that can be weighted with setoption, as percentages of their normal value
+const TERM_WEIGHT_OPTIONS: [&str; 5] = [
+    "Mobility",
+    "KingSafety",
+    "RookFiles",
+    "BishopPair",
+    "Outposts",
+];
+const MAX_TERM_WEIGHT: i32 = 400;
+// the pruning techniques that can be turned off with setoption
+const PRUNING_OPTIONS: [&str; 3] = ["LateMoveReductions", "NullMovePruning", "FutilityPruning"];
+
+#[cfg(test)]
+mod uci_tests;
+
+pub fn run() {
+    let line_receiver = spawn_input_reader();
+    let mut uci_engine = UciEngine::new(io::stdout());
+    loop {
+        match line_receiver.recv_timeout(POLL_INTERVAL) {
+            Ok(line) => {
+                if !uci_engine.handle_command(&line) {
+                    break;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => (),
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        uci_engine.poll_search();
+    }
+}
+
+// stdin is read on its own thread so that commands like "stop" can be received while searching
+fn spawn_input_reader() -> Receiver<String> {
+    let (line_sender, line_receiver) = channel();
+    thread::spawn(move || {
+        let stdin = io::stdin();
+        for line in stdin.lock().lines() {
+            match line {
+                Ok(line) => {
+                    if line_sender.send(line).is_err() {
+                        break;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+    });
+    line_receiver
+}
+
+struct UciEngine<W: Write> {
+    output: W,
+    board_state: BoardState,
+    chess_computer: BestActionFinder,
+    searching: bool,
+    // the protocol doesnt allow bestmove to be sent during go infinite until stop is
+    // received, so a search that finishes by itself before then keeps its result here
+    infinite: bool,
+    stop_received: bool,
+    held_result: Option<Result<SearchResult, &'static str>>,
+    // commands that change the position or the options wait until the search has sent its
+    // result, gui's are allowed to send them before they have received bestmove
+    queued_commands: VecDeque<String>,
+    term_weights: TermWeights,
+    pruning_options: PruningOptions,
+}
+
+impl<W: Write> UciEngine<W> {
+    fn new(output: W) -> UciEngine<W> {
+        UciEngine {
+            output,
+            board_state: BoardState::default(),
+            chess_computer: BestActionFinder::new(),
+            searching: false,
+            infinite: false,
+            stop_received: false,
+            held_result: None,
+            queued_commands: VecDeque::new(),
+            term_weights: TermWeights::default(),
+            pruning_options: PruningOptions::default(),
+        }
+    }
+
+    // returns false when the engine should quit
+    fn handle_command(&mut self, line: &str) -> bool {
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("uci") => {
+                self.send(format!("id name {}", ENGINE_NAME));
+                self.send(format!("id author {}", ENGINE_AUTHOR));
+                self.send(format!(
+                    "option name Hash type spin default {} min 1 max {}",
+                    DEFAULT_TRANSPOSITION_TABLE_SIZE_MB, MAX_HASH_SIZE_MB
+                ));
+                self.send(format!(
+                    "option name BookDepth type spin default {} min 0 max {}",
+                    DEFAULT_BOOK_DEPTH, MAX_BOOK_DEPTH
+                ));
+                for name in TERM_WEIGHT_OPTIONS.iter() {
+                    self.send(format!(
+                        "option name {} type spin default 100 min 0 max {}",
+                        name, MAX_TERM_WEIGHT
+                    ));
+                }
+                for name in PRUNING_OPTIONS.iter() {
+                    self.send(format!("option name {} type check default true", name));
+                }
+                self.send("uciok".to_string());
+            }
+            Some("isready") => self.send("readyok".to_string()),
+            Some("setoption") | Some("position") | Some("go") if self.searching => {
+                self.queued_commands.push_back(line.to_string());
+            }
+            Some("ucinewgame") => {
+                // the commands queued behind the search could start another one
+                while self.searching {
+                    self.finish_search();
+                }
+                self.board_state = BoardState::default();
+                self.chess_computer.new_game();
+            }
+            Some("setoption") => self.set_option(&tokens.collect::<Vec<&str>>()),
+            Some("position") => self.set_position(&tokens.collect::<Vec<&str>>()),
+            Some("go") => self.go(&tokens.collect::<Vec<&str>>()),
+            Some("stop") => self.finish_search(),
+            Some("quit") => {
+                self.chess_computer.stop();
+                return false;
+            }
+            // unknown commands are ignored as required by the protocol
+            _ => (),
+        }
+        true
+    }
+
+    fn set_option(&mut self, tokens: &[&str]) {
+        // option names can contain spaces so everything between "name" and "value" is the name
+        let name_index = tokens.iter().position(|token| *token == "name");
+        let value_index = tokens.iter().position(|token| *token == "value");
+        let (name, value) = match (name_index, value_index) {
+            (Some(name_index), Some(value_index)) if name_index < value_index => (
+                tokens[name_index + 1..value_index].join(" "),
+                tokens[value_index + 1..].join(" "),
+            ),
+            _ => {
+                self.send("info string invalid setoption command".to_string());
+                return;
+            }
+        };
+        if name.eq_ignore_ascii_case("Hash") {
+            match value.parse::<usize>() {
+                Ok(size_mb) if (1..=MAX_HASH_SIZE_MB).contains(&size_mb) => {
+                    self.chess_computer.set_transposition_table_size(size_mb)
+                }
+                _ => self.send(format!("info string invalid hash size {}", value)),
+            }
+        } else if name.eq_ignore_ascii_case("BookDepth") {
+            match value.parse::<u16>() {
+                Ok(book_depth) if book_depth <= MAX_BOOK_DEPTH => {
+                    self.chess_computer.set_book_depth(book_depth)
+                }
+                _ => self.send(format!("info string invalid book depth {}", value)),
+            }
+        } else if let Some(option) = TERM_WEIGHT_OPTIONS
+            .iter()
+            .find(|option| name.eq_ignore_ascii_case(option))
+        {
+            let weight = match value.parse::<i32>() {
+                Ok(weight) if (0..=MAX_TERM_WEIGHT).contains(&weight) => weight,
+                _ => {
+                    self.send(format!("info string invalid weight {}", value));
+                    return;
+                }
+            };
+            match *option {
+                "Mobility" => self.term_weights.mobility = weight,
+                "KingSafety" => self.term_weights.king_safety = weight,
+                "RookFiles" => self.term_weights.rook_files = weight,
+                "BishopPair" => self.term_weights.bishop_pair = weight,
+                _ => self.term_weights.outposts = weight,
+            }
+            self.chess_computer.set_term_weights(self.term_weights);
+        } else if let Some(option) = PRUNING_OPTIONS
+            .iter()
+            .find(|option| name.eq_ignore_ascii_case(option))
+        {
+            let enabled = match value.to_ascii_lowercase().as_str() {
+                "true" => true,
+                "false" => false,
+                _ => {
+                    self.send(format!("info string invalid check value {}", value));
+                    return;
+                }
+            };
+            match *option {
+                "LateMoveReductions" => self.pruning_options.late_move_reductions = enabled,
+                "NullMovePruning" => self.pruning_options.null_move_pruning = enabled,
+                _ => self.pruning_options.futility_pruning = enabled,
+            }
+            self.chess_computer
+                .set_pruning_options(self.pruning_options);
+        } else {
+            self.send(format!("info string unknown option {}", name));
+        }
+    }
+
+    fn set_position(&mut self, tokens: &[&str]) {
+        let moves_index = tokens.iter().position(|token| *token == "moves");
+        let position_tokens = &tokens[..moves_index.unwrap_or(tokens.len())];
+        let mut board_state = match position_tokens.first() {
+            Some(&"startpos") => BoardState::default(),
+            Some(&"fen") => match BoardState::from_fen(&position_tokens[1..].join(" ")) {
+                Ok(board_state) => board_state,
+                Err(err) => {
+                    self.send(format!("info string invalid fen: {}", err));
+                    return;
+                }
+            },
+            _ => {
+                self.send("info string invalid position command".to_string());
+                return;
+            }
+        };
+        if let Some(moves_index) = moves_index {
+            for move_text in &tokens[moves_index + 1..] {
+                match Action::from_uci(&board_state, move_text) {
+                    Ok(action) => action.play_move(&mut board_state),
+                    Err(err) => {
+                        self.send(format!("info string {}", err));
+                        break;
+                    }
+                }
+            }
+        }
+        self.board_state = board_state;
+    }
+
+    fn go(&mut self, tokens: &[&str]) {
+        let value_of = |name: &str| -> Option<u64> {
+            let index = tokens.iter().position(|token| *token == name)?;
+            tokens.get(index + 1)?.parse::<u64>().ok()
+        };
+        let (time_left, increment) = match self.board_state.color_turn {
+            PieceColor::White => (value_of("wtime"), value_of("winc")),
+            PieceColor::Black => (value_of("btime"), value_of("binc")),
+        };
+        let clock = time_left.map(|time_left| Clock {
+            time_left: Duration::from_millis(time_left),
+            increment: Duration::from_millis(increment.unwrap_or(0)),
+            moves_to_go: value_of("movestogo").map(|moves_to_go| moves_to_go as u32),
+        });
+        let move_time = value_of("movetime").map(Duration::from_millis);
+        let max_nodes = value_of("nodes");
+        // without a depth the search keeps deepening until another limit stops it
+        let max_depth = match value_of("depth") {
+            Some(depth) => depth.max(1).min(MAX_DEPTH as u64) as u8,
+            None if tokens.contains(&"infinite")
+                || clock.is_some()
+                || move_time.is_some()
+                || max_nodes.is_some() =>
+            {
+                MAX_DEPTH
+            }
+            None => DEFAULT_DEPTH,
+        };
+        let limits = SearchLimits {
+            max_depth,
+            max_nodes,
+            move_time,
+            clock,
+        };
+        self.searching = true;
+        self.infinite = tokens.contains(&"infinite");
+        self.stop_received = false;
+        self.chess_computer
+            .start_finding_move_with_limits(&self.board_state, limits);
+    }
+
+    fn poll_search(&mut self) {
+        if !self.searching {
+            return;
+        }
+        let state = self.chess_computer.get_state();
+        self.report_iterations();
+        match state {
+            State::Finished(result) if self.infinite && !self.stop_received => {
+                self.held_result = Some(result);
+            }
+            State::Finished(result) => self.report_result(result),
+            State::Thinking(_) | State::Idle => (),
+        }
+    }
+
+    // stops the search and waits for it to send its result
+    fn finish_search(&mut self) {
+        if !self.searching {
+            return;
+        }
+        self.stop_received = true;
+        self.chess_computer.stop();
+        let result = match self.held_result.take() {
+            Some(result) => result,
+            None => self.wait_for_result(),
+        };
+        self.report_result(result);
+    }
+
+    fn wait_for_result(&mut self) -> Result<SearchResult, &'static str> {
+        loop {
+            let state = self.chess_computer.get_state();
+            self.report_iterations();
+            if let State::Finished(result) = state {
+                return result;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    // an info line for each iteration the search has completed so the gui can show how
+    // the search is going
+    fn report_iterations(&mut self) {
+        for result in self.chess_computer.take_completed_iterations() {
+            let info = self.info(&result);
+            self.send(info);
+        }
+    }
+
+    fn info(&self, result: &SearchResult) -> String {
+        format!(
+            "info depth {} score {} nodes {} time {} pv {}",
+            result.depth,
+            format_score(result.score, self.board_state.color_turn),
+            result.nodes,
+            result.elapsed.as_millis(),
+            result.pv_to_uci(&self.board_state)
+        )
+    }
+
+    fn report_result(&mut self, result: Result<SearchResult, &'static str>) {
+        match result {
+            Ok(result) => {
+                let move_text = result.action.to_uci(&self.board_state);
+                let info = self.info(&result);
+                self.send(info);
+                self.send(format!("bestmove {}", move_text));
+            }
+            Err(err) => {
+                self.send(format!("info string {}", err));
+                self.send("bestmove 0000".to_string());
+            }
+        }
+        self.searching = false;
+        while !self.searching {
+            match self.queued_commands.pop_front() {
+                Some(line) => {
+                    self.handle_command(&line);
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn send(&mut self, line: String) {
+        writeln!(self.output, "{}", line).expect("could not write to the output");
+    }
+}
+
+// uci scores are relative to the side to move, evaluation scores are from blacks perspective
+fn format_score(score: Score, color_turn: PieceColor) -> String {
+    match mate_distance(score) {
+        Some((mating_color, plies)) => {
+            let moves = plies.div_ceil(2).max(1);
+            if mating_color == color_turn {
+                format!("mate {}", moves)
+            } else {
+                format!("mate -{}", moves)
+            }
+        }
+        None => match color_turn {
+            PieceColor::White => format!("cp {}", -(score as i32)),
+            PieceColor::Black => format!("cp {}", score),
+        },
+    }
+}
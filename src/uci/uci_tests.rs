@@ -0,0 +1,150 @@
+use super::{format_score, UciEngine};
+use crate::best_action_finder::{EndgameTableSearcher, Evaluator};
+use crate::{Action, BoardState, PieceColor};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const KING_AND_PAWN_FEN: &str = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1";
+const MIDDLEGAME_FEN: &str =
+    "r2qkb1r/ppp2ppp/2np1n2/4p3/2B1P1b1/2NP1N2/PPP2PPP/R1BQK2R w KQkq - 1 6";
+// long enough for any of the searches here to finish
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(60);
+
+// sends the commands to the engine then waits for its searches to finish, returning
+// everything the engine has written
+fn run(engine: &mut UciEngine<Vec<u8>>, commands: &[&str]) -> Vec<String> {
+    for command in commands {
+        engine.handle_command(command);
+    }
+    // go infinite only finishes once it is stopped
+    let start = Instant::now();
+    while engine.searching && (engine.stop_received || !engine.infinite) {
+        assert!(
+            start.elapsed() < SEARCH_TIMEOUT,
+            "the search did not finish"
+        );
+        thread::sleep(Duration::from_millis(10));
+        engine.poll_search();
+    }
+    output_lines(engine)
+}
+
+fn output_lines(engine: &UciEngine<Vec<u8>>) -> Vec<String> {
+    String::from_utf8(engine.output.clone())
+        .unwrap()
+        .lines()
+        .map(String::from)
+        .collect()
+}
+
+fn best_moves(lines: &[String]) -> Vec<&str> {
+    lines
+        .iter()
+        .filter(|line| line.starts_with("bestmove "))
+        .map(|line| &line["bestmove ".len()..])
+        .collect()
+}
+
+fn is_legal(fen: &str, uci: &str) -> bool {
+    Action::from_uci(&BoardState::from_fen(fen).unwrap(), uci).is_ok()
+}
+
+#[test]
+fn go_infinite_waits_for_stop() {
+    let mut engine = UciEngine::new(Vec::new());
+    let position = format!("position fen {}", KING_AND_PAWN_FEN);
+    run(&mut engine, &[&position, "go infinite"]);
+    // the endgame tables answer at once but the result is held back
+    thread::sleep(Duration::from_millis(200));
+    engine.poll_search();
+    assert!(best_moves(&output_lines(&engine)).is_empty());
+    let lines = run(&mut engine, &["stop"]);
+    assert_eq!(best_moves(&lines).len(), 1);
+    assert!(is_legal(KING_AND_PAWN_FEN, best_moves(&lines)[0]));
+}
+
+#[test]
+fn new_search_after_stop() {
+    let mut engine = UciEngine::new(Vec::new());
+    let first_position = format!("position fen {}", MIDDLEGAME_FEN);
+    let second_position = format!("position fen {}", KING_AND_PAWN_FEN);
+    let lines = run(
+        &mut engine,
+        &[
+            &first_position,
+            "go infinite",
+            "stop",
+            &second_position,
+            "go depth 3",
+        ],
+    );
+    let best_moves = best_moves(&lines);
+    assert_eq!(best_moves.len(), 2);
+    assert!(is_legal(MIDDLEGAME_FEN, best_moves[0]));
+    assert!(is_legal(KING_AND_PAWN_FEN, best_moves[1]));
+}
+
+#[test]
+fn commands_during_a_search_are_queued() {
+    let mut engine = UciEngine::new(Vec::new());
+    let first_position = format!("position fen {}", MIDDLEGAME_FEN);
+    let second_position = format!("position fen {}", KING_AND_PAWN_FEN);
+    let lines = run(
+        &mut engine,
+        &[
+            &first_position,
+            "go depth 3",
+            &second_position,
+            "go depth 2",
+        ],
+    );
+    let best_moves = best_moves(&lines);
+    assert_eq!(best_moves.len(), 2);
+    assert!(is_legal(MIDDLEGAME_FEN, best_moves[0]));
+    assert!(is_legal(KING_AND_PAWN_FEN, best_moves[1]));
+}
+
+#[test]
+fn ucinewgame_finishes_the_search_first() {
+    let mut engine = UciEngine::new(Vec::new());
+    let position = format!("position fen {}", MIDDLEGAME_FEN);
+    let lines = run(&mut engine, &[&position, "go infinite", "ucinewgame"]);
+    let best_moves = best_moves(&lines);
+    assert_eq!(best_moves.len(), 1);
+    assert!(is_legal(MIDDLEGAME_FEN, best_moves[0]));
+    assert!(!engine.searching);
+    assert_eq!(engine.board_state.to_fen(), BoardState::default().to_fen());
+}
+
+#[test]
+fn scores_are_relative_to_the_side_to_move() {
+    let evaluator = Evaluator::new(EndgameTableSearcher::new());
+    assert_eq!(format_score(150, PieceColor::Black), "cp 150");
+    assert_eq!(format_score(150, PieceColor::White), "cp -150");
+    // a big evaluation is still not a mate
+    assert_eq!(format_score(32000, PieceColor::Black), "cp 32000");
+    let white_mates_in_two = evaluator.score_for_checkmate(PieceColor::White, 3);
+    assert_eq!(
+        format_score(white_mates_in_two, PieceColor::White),
+        "mate 2"
+    );
+    assert_eq!(
+        format_score(white_mates_in_two, PieceColor::Black),
+        "mate -2"
+    );
+}
+
+#[test]
+fn info_line_for_each_completed_depth() {
+    let mut engine = UciEngine::new(Vec::new());
+    let position = format!("position fen {}", MIDDLEGAME_FEN);
+    let lines = run(&mut engine, &[&position, "go depth 3"]);
+    let depths: Vec<&str> = lines
+        .iter()
+        .filter(|line| line.starts_with("info depth "))
+        .map(|line| line.split_whitespace().nth(2).unwrap())
+        .collect();
+    // the last line repeats the deepest iteration with the totals for the whole search
+    assert_eq!(depths, vec!["1", "2", "3", "3"]);
+    assert_eq!(best_moves(&lines).len(), 1);
+}